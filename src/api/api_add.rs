@@ -1,3 +1,4 @@
+use reqwest::header;
 /// # Api Module: Add Task Operation
 ///
 /// This module provides functionality for adding tasks to the specified table.
@@ -9,11 +10,16 @@
 /// ## Methods
 ///
 /// - `add_task`: Method to add a task to the specified table.
+/// - `add_tasks`: Method to add a task for every line in the format `<name> | <due> | <group>`,
+///   a line that can't be parsed or added is reported in the returned `BatchReport` and the
+///   others are added anyway.
 ///
 ///   ### Parameters
 ///
 ///   - `tablename`: Name of the table where the task will be added.
-///   - `body`: HashMap containing key-value pairs representing the task details.
+///   - `body`: HashMap containing key-value pairs representing the task details, `description`
+///     is always there while `due`, `group`, `note` and `recurrence` are only present when the
+///     user gave them, so that the server applies its defaults for the missing ones.
 ///
 ///   ### Returns
 ///
@@ -23,16 +29,17 @@
 ///     either `SuccessfulResponse` or `ErrorResponse`.
 ///   - On failure, returns an `Error`.
 use std::collections::HashMap;
-use std::io::Read;
 
-use reqwest::{blocking, header};
-use serde_json::json;
-
-use crate::api::{ErrorResponse, SuccessfulResponse};
+use crate::api::{parse_response, table_route, task_payload, SuccessfulResponse};
 use crate::error::{Error, Result};
+use crate::parsers::{Due, TaskLine};
+use crate::utils::batch_report::BatchReport;
+use crate::utils::config_helper::Config;
+use crate::utils::normalize_description;
+use crate::utils::op_log::{record_op, Op};
 use crate::utils::table_formatter::FormattedResponse;
 
-use super::{Api, BACKEND};
+use super::Api;
 
 impl Api {
     pub fn add_task(
@@ -40,43 +47,84 @@ impl Api {
         tablename: String,
         body: HashMap<&str, &str>,
     ) -> Result<Box<dyn FormattedResponse>> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
-
-        let tablename = match tablename {
-            x if ["reminder", "todo"].contains(&x.as_str()) => x.to_owned(),
+        let client = self.client()?;
 
-            name => format!("user/{}", name),
-        };
+        let tablename = table_route(&tablename);
         let token: String = self.token.clone().unwrap_or_default().into();
-        let url = format!("{}/{}", BACKEND, tablename);
-        let body = json!(body).to_string();
+        let url = format!("{}/{}", self.backend, tablename);
+        let body = task_payload(&body, self.normalize_whitespace)?;
+
+        let response = self.send(
+            client
+                .post(url)
+                .header(header::COOKIE, token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body),
+        )?;
+
+        parse_response::<SuccessfulResponse>(response)
+    }
+
+    /// Adds a task for every line of `contents`, every task added is recorded in the op log
+    ///
+    /// # Args
+    /// - config: struct `Config` that represents the config management
+    /// - tablename: the table where to add the tasks
+    /// - contents: the lines, in the format `<name> | <due> | <group>`
+    /// - no_past_due: whether a line with a due already past fails instead of being added
+    pub fn add_tasks(
+        &self,
+        config: &Config,
+        tablename: &str,
+        contents: &str,
+        no_past_due: bool,
+    ) -> BatchReport {
+        let mut report = BatchReport::default();
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
 
-        let mut response = client
-            .post(url)
-            .header(header::COOKIE, token)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(body)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+            let task = match line.parse::<TaskLine>() {
+                Ok(task) => task,
+                Err(e) => {
+                    report.failure(format!("line {}", index + 1), e);
+                    continue;
+                }
+            };
+            if no_past_due && task.due.as_ref().is_some_and(Due::is_past) {
+                report.failure(&task.description, Error::PastDue.to_string());
+                continue;
+            }
 
-        let mut body = String::new();
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
+            let due = task.due.as_ref().map(|due| due.to_string());
+            let mut body: HashMap<&str, &str> = HashMap::new();
+            if let Some(due) = &due {
+                body.insert("due", due);
+            }
+            if let Some(group) = &task.group {
+                body.insert("group", group);
+            }
+            body.insert("description", &task.description);
 
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            let task_response: SuccessfulResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(task_response)
-        };
+            let res = self.add_task(tablename.to_owned(), body);
+            if let Ok(res) = &res {
+                if let Ok(description) =
+                    normalize_description(&task.description, self.normalize_whitespace)
+                {
+                    record_op(
+                        config,
+                        res.as_ref(),
+                        Op::Add {
+                            table: tablename.to_owned(),
+                            description,
+                        },
+                    );
+                }
+            }
+            report.record(&task.description, res);
+        }
 
-        Ok(json_response_obj)
+        report
     }
 }
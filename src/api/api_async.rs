@@ -0,0 +1,151 @@
+/// # Api Module: Async Client
+///
+/// This module provides a non-blocking version of the task operations, for the applications
+/// embedding the client in an async runtime. It is only built with the `async` feature and
+/// needs a tokio runtime, like `reqwest::Client`.
+///
+/// The urls, the bodies and the parsing of the answers are shared with the blocking `Api`,
+/// the async client doesn't retry, doesn't cache the tables and has no dry run.
+///
+/// ## Structs
+///
+/// - `AsyncApi`: The async counterpart of `Api`.
+///
+/// ## Methods
+///
+/// - `set_backend`: Method to override the backend url.
+/// - `get_tasks`: Method to fetch the tasks of a table, or the tables without one.
+/// - `add_task`: Method to add a task to a table.
+/// - `remove_task`: Method to remove a task from a table.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::header;
+use urlencoding::encode;
+
+use crate::error::{Error, Result};
+use crate::utils::config_helper::{Config, Token};
+use crate::utils::table_formatter::FormattedResponse;
+
+use super::api_list::{listing_url, parse_listing, TaskListing};
+use super::{
+    parse_body, request_error, resolve_backend, table_route, task_payload, SuccessfulResponse,
+    USER_AGENT,
+};
+
+pub struct AsyncApi {
+    token: Option<Token>,
+    backend: String,
+    /// Whether the inner whitespace of task descriptions gets collapsed before sending
+    normalize_whitespace: bool,
+    client: reqwest::Client,
+}
+
+impl AsyncApi {
+    /// Creates an authenticated api, the token and the settings are taken from the config
+    /// like `Api::new`
+    pub fn new(config: &Config) -> Result<AsyncApi> {
+        let token = Token::from(config.token.clone().ok_or(Error::NoAuth)?);
+        let client = reqwest::Client::builder()
+            .use_rustls_tls()
+            .user_agent(USER_AGENT)
+            .cookie_store(true)
+            .danger_accept_invalid_certs(config.insecure)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|_| Error::FailedToConnectToServer)?;
+
+        Ok(AsyncApi {
+            token: Some(token),
+            backend: resolve_backend(config.backend.as_deref()),
+            normalize_whitespace: config.normalize_whitespace,
+            client,
+        })
+    }
+
+    /// Overrides the backend url, like `Api::set_backend`
+    pub fn set_backend(&mut self, backend: String) {
+        self.backend = backend.trim_end_matches('/').to_owned();
+    }
+
+    fn cookie(&self) -> String {
+        self.token.clone().unwrap_or_default().into()
+    }
+
+    pub async fn get_tasks(
+        &self,
+        tablename: Option<&str>,
+        opts: HashMap<&str, &str>,
+    ) -> Result<TaskListing> {
+        let url = listing_url(&self.backend, tablename, &opts);
+
+        let response = self
+            .client
+            .get(url)
+            .header(header::COOKIE, self.cookie())
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|_| Error::InvalidServerResponse)?;
+        parse_listing(tablename, status, &body)
+    }
+
+    pub async fn add_task(
+        &self,
+        tablename: &str,
+        body: HashMap<&str, &str>,
+    ) -> Result<Box<dyn FormattedResponse>> {
+        let url = format!("{}/{}", self.backend, table_route(tablename));
+        let body = task_payload(&body, self.normalize_whitespace)?;
+
+        let response = self
+            .client
+            .post(url)
+            .header(header::COOKIE, self.cookie())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|_| Error::InvalidServerResponse)?;
+        parse_body::<SuccessfulResponse>(status, &body)
+    }
+
+    pub async fn remove_task(
+        &self,
+        tablename: &str,
+        desc: &str,
+    ) -> Result<Box<dyn FormattedResponse>> {
+        let url = format!(
+            "{}/{}/{}",
+            self.backend,
+            table_route(tablename),
+            encode(desc)
+        );
+
+        let response = self
+            .client
+            .delete(url)
+            .header(header::COOKIE, self.cookie())
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|_| Error::InvalidServerResponse)?;
+        parse_body::<SuccessfulResponse>(status, &body)
+    }
+}
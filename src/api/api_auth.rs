@@ -5,6 +5,8 @@
 /// ## Struct
 ///
 /// - `Api`: Implementation of the API structure.
+/// - `Session`: The session cookie set by the server at login, `to_cookie_header` turns it
+///   into the token kept in the config.
 ///
 /// ## Methods
 ///
@@ -12,215 +14,237 @@
 /// - `post_login`: Method to log in a user.
 /// - `post_logout`: Method to log out a user.
 /// - `post_lostkey`: Method to recover a lost key for a user.
-use std::io::Read;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
-use reqwest::{blocking, header};
+use reqwest::header;
 use serde_json::json;
 
-use super::{Api, ErrorResponse, SuccessfulResponse, BACKEND};
+use super::{parse_response, Api, SuccessfulResponse};
+use crate::utils::config_helper::{Token, COOKIE_EXPIRES_FORMAT};
 use crate::{
     error::{Error, Result},
     utils::table_formatter::FormattedResponse,
 };
 
+/// The longest a session is trusted to last, a bigger `Max-Age` is most likely a mistake of
+/// the server and would overflow the date
+const MAX_SESSION_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+
+/// The session cookie set by the server at login
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub name: String,
+    pub value: String,
+    pub path: String,
+    /// When the session expires, `None` when the server didn't say
+    pub expires: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    /// Reads the session from a cookie set by the server, the expiry is its `Expires`
+    /// attribute or, without one, its `Max-Age` from `now`
+    fn from_cookie(cookie: &reqwest::cookie::Cookie, now: DateTime<Utc>) -> Session {
+        Session {
+            name: cookie.name().to_owned(),
+            value: cookie.value().to_owned(),
+            path: cookie.path().unwrap_or("/").to_owned(),
+            expires: compute_expiry(cookie.expires().map(Into::into), cookie.max_age(), now),
+        }
+    }
+
+    /// Serializes the session as the cookie sent back to the server and kept in the config,
+    /// e.g. `id=abc; Path=/; HttpOnly; Expires=Sun, 02 Jun 2024 18:30:00 GMT`
+    pub fn to_cookie_header(&self) -> String {
+        let mut header = format!("{}={}; Path={}; HttpOnly", self.name, self.value, self.path);
+        if let Some(expires) = self.expires {
+            header.push_str(&format!(
+                "; Expires={}",
+                expires.format(COOKIE_EXPIRES_FORMAT)
+            ));
+        }
+        header
+    }
+}
+
+/// Returns when a session set at `now` expires: at `expires` when the cookie has it, otherwise
+/// `max_age` from `now` capped at `MAX_SESSION_AGE`, and `None` without either. A zero `max_age`
+/// (negative ones are read as zero) is a session already expired, it expires at `now`
+fn compute_expiry(
+    expires: Option<DateTime<Utc>>,
+    max_age: Option<Duration>,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    match (expires, max_age) {
+        (Some(expires), _) => Some(expires),
+        (None, Some(max_age)) => Some(now + max_age.min(MAX_SESSION_AGE)),
+        (None, None) => None,
+    }
+}
+
+impl From<Session> for Token {
+    fn from(session: Session) -> Token {
+        Token::from(session.to_cookie_header())
+    }
+}
+
 impl Api {
     // -- singup region
     pub fn post_signup(&self, usr: &str, pwd: &str) -> Result<Box<dyn FormattedResponse>> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+        let client = self.client()?;
 
         let token: String = self.token.clone().unwrap_or_default().into();
-        let url = format!("{}/signup", BACKEND);
+        let url = format!("{}/signup", self.backend);
         let payload = json!({
             "username": usr.trim(),
             "password": pwd.trim(),
         })
         .to_string();
 
-        let mut response = client
-            .post(url)
-            .header(header::COOKIE, token)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(payload)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
-
-        let mut body = String::new();
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
-
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            let success_response: SuccessfulResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(success_response)
-        };
-
-        Ok(json_response_obj)
+        let response = self.send(
+            client
+                .post(url)
+                .header(header::COOKIE, token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(payload),
+        )?;
+
+        parse_response::<SuccessfulResponse>(response)
     }
     // -- end singup region
 
     // -- login region
-    pub fn post_login(&self, key: &str) -> Result<(Box<dyn FormattedResponse>, String)> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+    /// Logs in with a key, the session is the cookie the server sets, `None` when it
+    /// didn't set any (e.g. the login failed). A session already expired is `Error::NoAuth`
+    pub fn post_login(&self, key: &str) -> Result<(Box<dyn FormattedResponse>, Option<Session>)> {
+        let client = self.client()?;
 
         let token: String = self.token.clone().unwrap_or_default().into();
-        let url = format!("{}/login", BACKEND);
+        let url = format!("{}/login", self.backend);
         let payload = json!({
             "key": key.trim(),
         })
         .to_string();
 
-        let mut response = client
-            .post(url)
-            .header(header::COOKIE, token)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(payload)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+        let response = self.send(
+            client
+                .post(url)
+                .header(header::COOKIE, token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(payload),
+        )?;
 
-        let token = response
+        let now = Utc::now();
+        let session = response
             .cookies()
-            .into_iter()
-            .map(|cookie| {
-                // tries to retrive the exp date in it cant it retrives the mag_age one and
-                // calculates it
-                let expires_string = match cookie.expires() {
-                    Some(expires) => {
-                        let datetime: DateTime<Utc> = expires.into();
-                        datetime.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
-                    }
-                    None => match cookie.max_age() {
-                        Some(duration) => {
-                            let datetime = Utc::now() + duration;
-                            datetime.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
-                        }
-                        None => String::from(""),
-                    },
-                };
-
-                format!(
-                    "{}={}; Path={}; HttpOnly; Expires={}",
-                    cookie.name(),
-                    cookie.value(),
-                    cookie.path().unwrap_or("/"),
-                    expires_string,
-                )
-            })
-            .collect::<Vec<String>>()
-            .join("; ");
-
-        let mut body = String::new();
-
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
-
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            let success_response: SuccessfulResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(success_response)
-        };
-
-        Ok((json_response_obj, token))
+            .next()
+            .map(|cookie| Session::from_cookie(&cookie, now));
+        // a session already expired (e.g. `Max-Age=0`) can't be used for anything
+        if session
+            .as_ref()
+            .and_then(|session| session.expires)
+            .is_some_and(|expires| expires <= now)
+        {
+            log::warn!("The server set a session already expired");
+            return Err(Error::NoAuth);
+        }
+
+        Ok((parse_response::<SuccessfulResponse>(response)?, session))
     }
     // -- end login region
 
     // -- logout region
     pub fn post_logout(&self, logout: bool) -> Result<Box<dyn FormattedResponse>> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+        let client = self.client()?;
 
         let token: String = self.token.clone().unwrap_or_default().into();
-        let url = format!("{}/logout", BACKEND);
+        let url = format!("{}/logout", self.backend);
         let payload = json!({
             "logout": logout
         })
         .to_string();
 
-        let mut response = client
-            .post(url)
-            .header(header::COOKIE, token)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(payload)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
-
-        let mut body = String::new();
-
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
-
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            let success_response: SuccessfulResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(success_response)
-        };
-
-        Ok(json_response_obj)
+        let response = self.send(
+            client
+                .post(url)
+                .header(header::COOKIE, token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(payload),
+        )?;
+
+        parse_response::<SuccessfulResponse>(response)
     }
     // -- end logout region
 
     // -- lostkey region
     pub fn post_lostkey(&self, usr: &str, pwd: &str) -> Result<Box<dyn FormattedResponse>> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+        let client = self.client()?;
 
         let token: String = self.token.clone().unwrap_or_default().into();
-        let url = format!("{}/lostkey", BACKEND);
+        let url = format!("{}/lostkey", self.backend);
         let payload = json!({
             "username": usr.trim(),
             "password": pwd.trim(),
         })
         .to_string();
 
-        let mut response = client
-            .post(url)
-            .header(header::COOKIE, token)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(payload)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
-
-        let mut body = String::new();
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
-
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            let success_response: SuccessfulResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(success_response)
-        };
-
-        Ok(json_response_obj)
+        let response = self.send(
+            client
+                .post(url)
+                .header(header::COOKIE, token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(payload),
+        )?;
+
+        parse_response::<SuccessfulResponse>(response)
     }
     // -- end lostkey region
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2030, 6, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn an_explicit_expires_wins_over_max_age() {
+        let expires = Utc.with_ymd_and_hms(2030, 6, 2, 18, 30, 0).unwrap();
+
+        let expiry = compute_expiry(Some(expires), Some(Duration::from_secs(60)), now());
+
+        assert_eq!(expiry, Some(expires));
+    }
+
+    #[test]
+    fn max_age_counts_from_now() {
+        let expiry = compute_expiry(None, Some(Duration::from_secs(3600)), now());
+
+        assert_eq!(
+            expiry,
+            Some(Utc.with_ymd_and_hms(2030, 6, 1, 13, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn max_age_is_capped() {
+        let expiry = compute_expiry(None, Some(Duration::from_secs(u64::MAX)), now());
+
+        assert_eq!(expiry, Some(now() + MAX_SESSION_AGE));
+    }
+
+    #[test]
+    fn a_zero_max_age_expires_now() {
+        let expiry = compute_expiry(None, Some(Duration::ZERO), now());
+
+        assert_eq!(expiry, Some(now()));
+    }
+
+    #[test]
+    fn without_expires_nor_max_age_there_is_no_expiry() {
+        assert_eq!(compute_expiry(None, None, now()), None);
+    }
+}
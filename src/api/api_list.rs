@@ -8,44 +8,234 @@
 /// - `TableCharacteristicsResponseDetails`: Details of table characteristics.
 /// - `GetTaskResponse`: Represents the response containing tasks.
 /// - `GetTaskResponseDetail`: Details of a task.
+/// - `TaskStats`: The counts of the tasks of a table, computed on the client by `stats`.
+///
+/// ## Enums
+///
+/// - `TaskListing`: What `get_tasks` fetched, the tasks of a table, the tables or an error.
+///
+/// `GetTaskResponse` also exposes `stats` to count the tasks, `retain_due_between`, `retain_group`, `retain_matching`
+/// and `retain_overdue` to filter the tasks, `paginate` to cut a page when the server doesn't,
+/// `sort_by` to sort them on the client when the server doesn't and
+/// `GetTaskResponseDetail` exposes `is_overdue`, `next_occurrence` for the tasks that repeat
+/// and `body` to recreate a task.
+///
+/// ## Functions
+///
+/// - `check_due_window`: Checks the bounds given to `retain_due_between`.
 ///
 /// ## Methods
 ///
-/// - `get_tasks`: Method to fetch tasks and table characteristics.
-use chrono::NaiveDateTime;
-use reqwest::{blocking, header};
+/// - `get_tasks`: Method to fetch tasks and table characteristics, the printing is left to the
+///   caller. The tables fetched are cached.
+/// - `cached_tables`: Method to get the tables from the cache, they are fetched again when the
+///   cache is stale.
+use chrono::{NaiveDateTime, Utc};
+use reqwest::{header, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
-use std::collections::HashMap;
-use std::io::Read;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::error::{Error, Result};
-use crate::utils::table_formatter::FormattedResponse;
+use crate::parsers::{Due, Recurrence, SortField, SortKey};
+use crate::utils::table_cache::{TableCache, TABLE_CACHE_TTL};
+use crate::utils::timezone::zone;
+
+use super::{parse_typed_body, table_route, Api, ErrorResponse};
 
-use super::{Api, ErrorResponse, BACKEND};
+/// The result of `get_tasks`, the tasks when a table is given and the tables otherwise
+pub enum TaskListing {
+    Tasks(GetTaskResponse),
+    Tables(TableCharacteristicsResponse),
+    ApiError(ErrorResponse),
+}
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct TableCharacteristicsResponse {
     pub res: Vec<TableCharacteristicsResponseDetails>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct TableCharacteristicsResponseDetails {
     pub has_due: bool,
     pub name: String,
+    /// The number of tasks in the table, only filled when explicitly requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tasks: Option<usize>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct GetTaskResponse {
     pub res: Vec<GetTaskResponseDetail>,
+    /// How many tasks the table has, only sent by the server when a page is asked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    /// The position in the table of the first task, set by the caller when a page is asked
+    #[serde(skip)]
+    pub offset: usize,
+    /// Whether the table shows the dues as dates instead of relative to now, set by the caller
+    #[serde(skip)]
+    pub absolute_dues: bool,
+}
+
+/// The counts of the tasks of a table, the tasks without a due are neither overdue nor due today
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct TaskStats {
+    pub total: usize,
+    pub overdue: usize,
+    /// The tasks due later today, the ones already past are counted as overdue
+    pub due_today: usize,
+    pub by_group: BTreeMap<String, usize>,
+}
+
+impl GetTaskResponseDetail {
+    /// Whether the task is past its due, tasks without a due are never overdue
+    pub fn is_overdue(&self, now: NaiveDateTime) -> bool {
+        self.due.is_some_and(|due| due < now)
+    }
+
+    /// Returns the formatted due of the next occurrence of a task that repeats, `None` for
+    /// tasks without a due or a recurrence. The occurrences keep the wall clock time across
+    /// the daylight saving changes, a next one falling in a DST gap keeps the UTC time instead
+    pub fn next_occurrence(&self) -> Option<String> {
+        let recurrence = self.recurrence.as_deref()?.parse::<Recurrence>().ok()?;
+        let due = self.due?;
+        let next = recurrence
+            .next_due(zone().from_server(due))
+            .and_then(|next| zone().to_server(next).ok())
+            .or_else(|| recurrence.next_due(due))?;
+        Some(Due(next).to_string())
+    }
+
+    /// Returns the body that recreates the task, `due` is the due of the task already
+    /// formatted
+    pub fn body<'a>(&'a self, due: &'a Option<String>) -> HashMap<&'a str, &'a str> {
+        let mut body: HashMap<&str, &str> = HashMap::new();
+        if let Some(due) = due {
+            body.insert("due", due);
+        }
+        body.insert("group", &self.group);
+        if let Some(note) = &self.note {
+            body.insert("note", note);
+        }
+        if let Some(recurrence) = &self.recurrence {
+            body.insert("recurrence", recurrence);
+        }
+        body.insert("description", &self.description);
+        body
+    }
+}
+
+impl GetTaskResponse {
+    /// Counts the tasks of the response, overdue and due today are relative to `now` (as the
+    /// server stores it), today is the day of `now` in the configured timezone
+    pub fn stats(&self, now: NaiveDateTime) -> TaskStats {
+        let mut stats = TaskStats {
+            total: self.res.len(),
+            ..TaskStats::default()
+        };
+        let today = zone().from_server(now).date();
+        for task in &self.res {
+            match task.due {
+                Some(due) if due < now => stats.overdue += 1,
+                Some(due) if zone().from_server(due).date() == today => stats.due_today += 1,
+                _ => {}
+            }
+            *stats.by_group.entry(task.group.clone()).or_default() += 1;
+        }
+        stats
+    }
+
+    /// Keeps only the tasks due inside the given bounds (both inclusive),
+    /// tasks without a due are dropped as soon as a bound is set. The bounds are expected to
+    /// be checked with `check_due_window`
+    pub fn retain_due_between(
+        &mut self,
+        since: Option<NaiveDateTime>,
+        until: Option<NaiveDateTime>,
+    ) {
+        if since.is_none() && until.is_none() {
+            return;
+        }
+
+        self.res.retain(|task| match task.due {
+            Some(due) => {
+                since.is_none_or(|since| due >= since) && until.is_none_or(|until| due <= until)
+            }
+            None => false,
+        });
+    }
+
+    /// Keeps only the tasks overdue at `now` (as the server stores it), the most overdue
+    /// first, the tasks without a due are dropped
+    pub fn retain_overdue(&mut self, now: NaiveDateTime) {
+        self.res.retain(|task| task.is_overdue(now));
+        self.res.sort_by_key(|task| task.due);
+    }
+
+    /// Keeps only the tasks of the given group
+    pub fn retain_group(&mut self, group: &str) {
+        self.res.retain(|task| task.group == group);
+    }
+
+    /// Keeps only the tasks whose description contains `query`, case insensitive
+    pub fn retain_matching(&mut self, query: &str) {
+        let query = query.to_lowercase();
+        self.res
+            .retain(|task| task.description.to_lowercase().contains(&query));
+    }
+
+    /// Keeps the page of `size` tasks starting at `offset` when the server sent no total, i.e.
+    /// it doesn't support paging, the total is then the number of tasks sent. A page past the
+    /// end of the table is empty
+    pub fn paginate(&mut self, offset: usize, size: usize) {
+        self.offset = offset;
+        if self.total.is_some() {
+            return;
+        }
+
+        self.total = Some(self.res.len());
+        self.res = self.res.drain(..).skip(offset).take(size).collect();
+    }
+
+    /// Sorts the tasks by the given key, the sort is stable and tasks without a due
+    /// always go last when sorting by due, whatever the direction
+    pub fn sort_by(&mut self, key: SortKey) {
+        self.res.sort_by(|a, b| {
+            let ordering = match key.field {
+                SortField::Due => match (a.due, b.due) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    (Some(_), None) => return Ordering::Less,
+                    (None, Some(_)) => return Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                },
+                SortField::Group => a.group.cmp(&b.group),
+                SortField::Description => a.description.cmp(&b.description),
+            };
+            match key.descending {
+                true => ordering.reverse(),
+                false => ordering,
+            }
+        });
+    }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[skip_serializing_none]
 pub struct GetTaskResponseDetail {
     pub description: String,
     pub group: String,
     pub due: Option<NaiveDateTime>,
+    /// A longer note attached to the task, servers without notes never send it
+    #[serde(default)]
+    pub note: Option<String>,
+    /// How often the task repeats (e.g. `1w`), servers without recurrences never send it
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    /// Servers without a completion status never send it
+    #[serde(default)]
+    pub completed: bool,
 }
 
 impl Api {
@@ -53,69 +243,104 @@ impl Api {
         &self,
         tablename: Option<&str>,
         opts: HashMap<&str, &str>,
-    ) -> Result<Box<dyn FormattedResponse>> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+    ) -> Result<TaskListing> {
+        let client = self.client()?;
 
         let token: String = self.token.clone().unwrap_or_default().into();
-        let table = match tablename {
-            Some(name) => {
-                if ["reminder", "todo"].contains(&name) {
-                    name.to_owned()
-                } else {
-                    format!("user/{}", name)
-                }
-            }
-            None => "list".to_owned(),
-        };
+        let url = listing_url(&self.backend, tablename, &opts);
+
+        let response = self.send_idempotent(client.get(url).header(header::COOKIE, token))?;
 
-        let mut url = format!("{}/{}", BACKEND, table);
+        let status = response.status();
+        let body = response.text().map_err(|_| Error::InvalidServerResponse)?;
 
-        if !opts.is_empty() {
-            let mut encoded_params = String::new();
-            for (key, value) in opts.iter() {
-                let encoded_key = urlencoding::encode(key);
-                let encoded_value = urlencoding::encode(value);
-                encoded_params.push_str(&format!("{}={}&", encoded_key, encoded_value));
+        let listing = parse_listing(tablename, status, &body)?;
+        // a listing filtered by the server isn't all the tables
+        if let TaskListing::Tables(tables) = &listing {
+            if opts.is_empty() {
+                TableCache::load(self.ephemeral).store(tables, Utc::now());
             }
-            // remove trailing '&'
-            encoded_params.pop();
-            url.push_str(&format!("?{}", encoded_params));
         }
+        Ok(listing)
+    }
 
-        let mut response = client
-            .get(url)
-            .header(header::COOKIE, token)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
-
-        let mut body = String::new();
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
-
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            match tablename {
-                Some(_) => {
-                    let task_response: GetTaskResponse = serde_json::from_str(&body)
-                        .map_err(|_| Error::FailedtoReadServerResponse)?;
-                    Box::new(task_response)
-                }
-                None => {
-                    let table_char_response: TableCharacteristicsResponse =
-                        serde_json::from_str(&body)
-                            .map_err(|_| Error::FailedtoReadServerResponse)?;
-                    Box::new(table_char_response)
-                }
-            }
-        };
+    /// Returns the tables from the cache, or fetches them when the cache is older than
+    /// `TABLE_CACHE_TTL`. Meant for the checks done before a request, a table created since
+    /// the tables were cached isn't there
+    pub fn cached_tables(&self) -> Result<Vec<TableCharacteristicsResponseDetails>> {
+        if let Some(tables) =
+            TableCache::load(self.ephemeral).fresh_tables(Utc::now(), TABLE_CACHE_TTL)
+        {
+            return Ok(tables.to_vec());
+        }
+
+        match self.get_tasks(None, HashMap::new())? {
+            TaskListing::Tables(tables) => Ok(tables.res),
+            TaskListing::ApiError(err) => Err(err.into()),
+            TaskListing::Tasks(_) => Err(Error::InvalidServerResponse),
+        }
+    }
+}
+
+/// Checks that a due window doesn't end before it starts, a window with a single bound
+/// (or none) is always valid
+pub fn check_due_window(since: Option<NaiveDateTime>, until: Option<NaiveDateTime>) -> Result<()> {
+    match (since, until) {
+        (Some(since), Some(until)) if since > until => Err(Error::InvalidDueWindow),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the url listing the tasks of a table, or the tables without one, with the options
+/// as the query
+pub(super) fn listing_url(
+    backend: &str,
+    tablename: Option<&str>,
+    opts: &HashMap<&str, &str>,
+) -> String {
+    let table = match tablename {
+        Some(name) => table_route(name),
+        None => "list".to_owned(),
+    };
+
+    let mut url = format!("{}/{}", backend, table);
+
+    if !opts.is_empty() {
+        let mut encoded_params = String::new();
+        for (key, value) in opts.iter() {
+            let encoded_key = urlencoding::encode(key);
+            let encoded_value = urlencoding::encode(value);
+            encoded_params.push_str(&format!("{}={}&", encoded_key, encoded_value));
+        }
+        // remove trailing '&'
+        encoded_params.pop();
+        url.push_str(&format!("?{}", encoded_params));
+    }
+    url
+}
 
-        Ok(json_response_obj)
+/// Reads the answer to a listing, the tasks when a table was asked and the tables otherwise
+pub(super) fn parse_listing(
+    tablename: Option<&str>,
+    status: StatusCode,
+    body: &str,
+) -> Result<TaskListing> {
+    // an empty table may come back as an empty body instead of an empty `res`
+    if status.is_success() && body.trim().is_empty() {
+        return Ok(match tablename {
+            Some(_) => TaskListing::Tasks(GetTaskResponse::default()),
+            None => TaskListing::Tables(TableCharacteristicsResponse { res: vec![] }),
+        });
     }
+
+    Ok(match tablename {
+        Some(_) => match parse_typed_body::<GetTaskResponse>(status, body)? {
+            Ok(tasks) => TaskListing::Tasks(tasks),
+            Err(err_response) => TaskListing::ApiError(err_response),
+        },
+        None => match parse_typed_body::<TableCharacteristicsResponse>(status, body)? {
+            Ok(tables) => TaskListing::Tables(tables),
+            Err(err_response) => TaskListing::ApiError(err_response),
+        },
+    })
 }
@@ -0,0 +1,116 @@
+/// # Api Module: Lookups
+///
+/// This module finds the tasks and the tables the commands work on, over the listing
+/// requests. Nothing is printed and no spinner is shown, that is left to the caller.
+///
+/// ## Methods
+///
+/// - `fetch_tasks`: Method to fetch the tasks of a table, an answer of the server that isn't
+///   the tasks is an error.
+/// - `find_task`: Method to find a task by its full description.
+/// - `find_task_by_name`: Method to find the only task whose description contains a text.
+/// - `find_table`: Method to find the characteristics of a table, from the cache if possible.
+/// - `table_has_due`: Method to check whether a table supports dues.
+/// - `snapshot_table`: Method to take a snapshot of a table before it gets dropped, so that
+///   `undo` can create it again.
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::api::api_list::{
+    GetTaskResponse, GetTaskResponseDetail, TableCharacteristicsResponseDetails, TaskListing,
+};
+use crate::error::{Error, Result};
+use crate::utils::op_log::Op;
+use crate::utils::table_cache::{TableCache, TABLE_CACHE_TTL};
+
+use super::Api;
+
+impl Api {
+    pub fn fetch_tasks(&self, tablename: &str) -> Result<GetTaskResponse> {
+        match self.get_tasks(Some(tablename), HashMap::new())? {
+            TaskListing::Tasks(tasks) => Ok(tasks),
+            TaskListing::ApiError(err) => Err(err.into()),
+            TaskListing::Tables(_) => Err(Error::InvalidServerResponse),
+        }
+    }
+
+    pub fn find_task(&self, tablename: &str, desc: &str) -> Result<GetTaskResponseDetail> {
+        self.fetch_tasks(tablename)?
+            .res
+            .into_iter()
+            .find(|task| task.description == desc)
+            .ok_or(Error::TaskNotFound)
+    }
+
+    /// Returns the only task whose description contains `name` (case insensitive), when more
+    /// tasks match the error lists them
+    pub fn find_task_by_name(&self, tablename: &str, name: &str) -> Result<GetTaskResponseDetail> {
+        let name = name.to_lowercase();
+        let mut matches: Vec<GetTaskResponseDetail> = self
+            .fetch_tasks(tablename)?
+            .res
+            .into_iter()
+            .filter(|task| task.description.to_lowercase().contains(&name))
+            .collect();
+
+        match matches.len() {
+            0 => Err(Error::TaskNotFound),
+            1 => Ok(matches.remove(0)),
+            _ => Err(Error::AmbiguousTask {
+                name,
+                matches: matches.into_iter().map(|task| task.description).collect(),
+            }),
+        }
+    }
+
+    /// Returns the characteristics of a table, `None` when it doesn't exist. The tables come
+    /// from the cache when it is fresh, they are fetched when it is stale or when the table
+    /// isn't in it, it may have been created since they were cached
+    pub fn find_table(
+        &self,
+        tablename: &str,
+    ) -> Result<Option<TableCharacteristicsResponseDetails>> {
+        let cached = TableCache::load(self.ephemeral)
+            .fresh_tables(Utc::now(), TABLE_CACHE_TTL)
+            .and_then(|tables| tables.iter().find(|table| table.name == tablename).cloned());
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        match self.get_tasks(None, HashMap::new())? {
+            TaskListing::Tables(tables) => {
+                Ok(tables.res.into_iter().find(|table| table.name == tablename))
+            }
+            TaskListing::ApiError(err) => Err(err.into()),
+            TaskListing::Tasks(_) => Err(Error::InvalidServerResponse),
+        }
+    }
+
+    /// Returns whether a table supports dues, `TableNotFound` when it doesn't exist
+    pub fn table_has_due(&self, tablename: &str) -> Result<bool> {
+        self.find_table(tablename)?
+            .map(|table| table.has_due)
+            .ok_or(Error::TableNotFound)
+    }
+
+    /// Returns a table and its tasks as the operation that drops it, `None` if it can't be
+    /// fetched
+    pub fn snapshot_table(&self, tablename: &str) -> Option<Op> {
+        let TaskListing::Tables(tables) = self.get_tasks(None, HashMap::new()).ok()? else {
+            return None;
+        };
+        let has_due = tables
+            .res
+            .iter()
+            .find(|table| table.name == tablename)?
+            .has_due;
+        let tasks = self.fetch_tasks(tablename).ok()?.res;
+
+        Some(Op::Drop {
+            table: tablename.to_owned(),
+            has_due,
+            tasks,
+        })
+    }
+}
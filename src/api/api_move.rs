@@ -0,0 +1,51 @@
+/// # Api Module: Task Move Operation
+///
+/// This module provides functionality for moving tasks between tables.
+///
+/// ## Methods
+///
+/// - `move_task`: Method to move a task to another table.
+///
+/// The server has no move route, the task is added to the destination and then removed from
+/// the source. When the removal fails the copy in the destination is removed again, so the
+/// task is never left in both tables.
+use std::collections::HashMap;
+
+use crate::api::SuccessfulResponse;
+use crate::error::{Error, Result};
+use crate::utils::table_formatter::FormattedResponse;
+
+use super::Api;
+
+impl Api {
+    pub fn move_task(
+        &self,
+        from: String,
+        to: String,
+        body: HashMap<&str, &str>,
+    ) -> Result<Box<dyn FormattedResponse>> {
+        let desc = body
+            .get("description")
+            .map(|desc| desc.to_string())
+            .ok_or(Error::EmptyTaskName)?;
+
+        let added = self.add_task(to.clone(), body)?;
+        if !added.as_any().is::<SuccessfulResponse>() {
+            return Ok(added);
+        }
+
+        let removed = self.remove_task(from.clone(), desc.clone());
+        if !matches!(&removed, Ok(res) if res.as_any().is::<SuccessfulResponse>()) {
+            log::error!("Failed to remove '{desc}' from {from}, removing the copy from {to}");
+            if let Err(e) = self.remove_task(to.clone(), desc.clone()) {
+                log::error!("Failed to roll back the move of '{desc}': {e:?}");
+            }
+            return removed;
+        }
+
+        Ok(Box::new(SuccessfulResponse {
+            res: format!("Moved '{desc}' from '{from}' to '{to}'"),
+            req_uuid: None,
+        }))
+    }
+}
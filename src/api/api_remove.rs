@@ -5,16 +5,17 @@
 /// ## Methods
 ///
 /// - `remove_task`: Method to remove a task from a specified table.
-use std::io::Read;
-
-use reqwest::{blocking, header};
+/// - `remove_tasks`: Method to remove several tasks from a table, a failure doesn't stop the
+///   others and the outcome of each is in the returned `BatchReport`.
+use reqwest::header;
 use urlencoding::encode;
 
-use crate::api::{ErrorResponse, SuccessfulResponse};
-use crate::error::{Error, Result};
+use crate::api::{parse_response, table_route, SuccessfulResponse};
+use crate::error::Result;
+use crate::utils::batch_report::BatchReport;
 use crate::utils::table_formatter::FormattedResponse;
 
-use super::{Api, BACKEND};
+use super::Api;
 
 impl Api {
     pub fn remove_task(
@@ -22,41 +23,24 @@ impl Api {
         tablename: String,
         desc: String,
     ) -> Result<Box<dyn FormattedResponse>> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
-
-        let tablename = match tablename {
-            x if ["reminder", "todo"].contains(&x.as_str()) => x.to_owned(),
+        let client = self.client()?;
 
-            name => format!("user/{}", name),
-        };
+        let tablename = table_route(&tablename);
         let token: String = self.token.clone().unwrap_or_default().into();
         let url_encoded_desc = encode(&desc);
-        let url = format!("{}/{}/{}", BACKEND, tablename, url_encoded_desc);
+        let url = format!("{}/{}/{}", self.backend, tablename, url_encoded_desc);
 
-        let mut response = client
-            .delete(url)
-            .header(header::COOKIE, token)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+        let response = self.send(client.delete(url).header(header::COOKIE, token))?;
 
-        let mut body = String::new();
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
-
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            let task_response: SuccessfulResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(task_response)
-        };
+        parse_response::<SuccessfulResponse>(response)
+    }
 
-        Ok(json_response_obj)
+    pub fn remove_tasks(&self, tablename: &str, descs: &[String]) -> BatchReport {
+        let mut report = BatchReport::default();
+        for desc in descs {
+            let res = self.remove_task(tablename.to_owned(), desc.clone());
+            report.record(desc, res);
+        }
+        report
     }
 }
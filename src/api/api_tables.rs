@@ -1,98 +1,189 @@
 /// # Api Module: Table Operations
 ///
-/// This module provides functionality for creating and removing tables.
+/// This module provides functionality for creating, renaming and removing tables.
 ///
 /// ## Methods
 ///
-/// - `create_table`: Method to create a new table with optional due time.
+/// - `create_table`: Method to create a new table with optional due time and groups.
+/// - `rename_table`: Method to rename a table, on servers without a rename route the table is
+///   recreated with the new name, its tasks are copied and the old one is dropped. When a step
+///   fails the new table is dropped again, so the tasks are never lost.
 /// - `remove_table`: Method to remove an existing table.
-use std::io::Read;
+///
+/// The built-in `reminder` and `todo` tables can't be renamed or removed, they are refused
+/// without contacting the server. All of them drop the cache of the tables, whatever the
+/// server answers.
+use std::collections::HashMap;
 
-use reqwest::{blocking, header};
+use reqwest::{header, StatusCode};
 use serde_json::json;
+use urlencoding::encode;
 
+use crate::parsers::Due;
+use crate::utils::table_cache::TableCache;
 use crate::utils::table_formatter::FormattedResponse;
+use crate::utils::validate_tablename;
 
 use crate::error::{Error, Result};
 
-use super::{Api, ErrorResponse, SuccessfulResponse, BACKEND};
+use super::api_list::TaskListing;
+use super::{parse_response, Api, SuccessfulResponse};
 
 impl Api {
     pub fn create_table(
         &self,
         tablename: String,
         has_due: bool,
+        has_group: bool,
     ) -> Result<Box<dyn FormattedResponse>> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+        let client = self.client()?;
 
         let token: String = self.token.clone().unwrap_or_default().into();
-        let url = format!("{}/{}", BACKEND, tablename.trim());
+        let url = format!("{}/{}", self.backend, encode(tablename.trim()));
         let payload = json!({
-            "due_time": has_due
+            "due_time": has_due,
+            "has_group": has_group,
         })
         .to_string();
 
-        let mut response = client
-            .post(url)
-            .header(header::COOKIE, token)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(payload)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
-
-        let mut body = String::new();
-
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
-
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            let success_response: SuccessfulResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(success_response)
-        };
+        let response = self.send(
+            client
+                .post(url)
+                .header(header::COOKIE, token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(payload),
+        )?;
+        TableCache::load(self.ephemeral).invalidate();
 
-        Ok(json_response_obj)
+        parse_response::<SuccessfulResponse>(response)
     }
 
     pub fn remove_table(&self, tablename: String) -> Result<Box<dyn FormattedResponse>> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+        // the built-in tables are part of every account and can't be dropped
+        if ["reminder", "todo"].contains(&tablename.trim()) {
+            return Err(Error::BuiltinTable);
+        }
+
+        let client = self.client()?;
 
         let token: String = self.token.clone().unwrap_or_default().into();
-        let url = format!("{}/{}", BACKEND, tablename.trim());
-
-        let mut response = client
-            .delete(url)
-            .header(header::COOKIE, token)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
-
-        let mut body = String::new();
-
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
-
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            let success_response: SuccessfulResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(success_response)
+        let url = format!("{}/{}", self.backend, encode(tablename.trim()));
+
+        let response = self.send(client.delete(url).header(header::COOKIE, token))?;
+        TableCache::load(self.ephemeral).invalidate();
+
+        parse_response::<SuccessfulResponse>(response)
+    }
+
+    pub fn rename_table(&self, old: &str, new: &str) -> Result<Box<dyn FormattedResponse>> {
+        let old = old.trim();
+        if ["reminder", "todo"].contains(&old) {
+            return Err(Error::BuiltinTable);
+        }
+        let new = validate_tablename(new)?;
+
+        let client = self.client()?;
+
+        let token: String = self.token.clone().unwrap_or_default().into();
+        let url = format!("{}/{}", self.backend, encode(old));
+        let payload = json!({ "name": new }).to_string();
+
+        let response = self.send(
+            client
+                .patch(url)
+                .header(header::COOKIE, token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(payload),
+        )?;
+        TableCache::load(self.ephemeral).invalidate();
+
+        // the servers without the route answer as for any unknown route or method
+        if [
+            StatusCode::NOT_FOUND,
+            StatusCode::METHOD_NOT_ALLOWED,
+            StatusCode::NOT_IMPLEMENTED,
+        ]
+        .contains(&response.status())
+        {
+            log::info!("The server can't rename tables, recreating '{old}' as '{new}'");
+            return self.recreate_table(old, &new);
+        }
+
+        parse_response::<SuccessfulResponse>(response)
+    }
+
+    /// Renames a table by creating the new one, copying the tasks and dropping the old one,
+    /// the new table is dropped again when a step fails
+    fn recreate_table(&self, old: &str, new: &str) -> Result<Box<dyn FormattedResponse>> {
+        let has_due = match self.get_tasks(None, HashMap::new())? {
+            TaskListing::Tables(tables) => {
+                tables
+                    .res
+                    .iter()
+                    .find(|table| table.name == old)
+                    .ok_or(Error::TableNotFound)?
+                    .has_due
+            }
+            listing => return Ok(Box::new(listing)),
+        };
+        let tasks = match self.get_tasks(Some(old), HashMap::new())? {
+            TaskListing::Tasks(tasks) => tasks,
+            listing => return Ok(Box::new(listing)),
+        };
+
+        // the server doesn't tell whether a table has groups, every task has one anyway
+        let created = self.create_table(new.to_owned(), has_due, true)?;
+        if !created.as_any().is::<SuccessfulResponse>() {
+            return Ok(created);
+        }
+
+        let copy = || -> Result<Option<Box<dyn FormattedResponse>>> {
+            for task in &tasks.res {
+                let due = task.due.map(|due| Due(due).to_string());
+                let mut body: HashMap<&str, &str> = HashMap::new();
+                if let Some(due) = &due {
+                    body.insert("due", due);
+                }
+                body.insert("group", &task.group);
+                if let Some(note) = &task.note {
+                    body.insert("note", note);
+                }
+                if let Some(recurrence) = &task.recurrence {
+                    body.insert("recurrence", recurrence);
+                }
+                body.insert("description", &task.description);
+
+                let added = self.add_task(new.to_owned(), body)?;
+                if !added.as_any().is::<SuccessfulResponse>() {
+                    return Ok(Some(added));
+                }
+                if task.completed {
+                    match self.complete_task(new, &task.description) {
+                        Ok(_) | Err(Error::CompletionUnsupported) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+
+            let dropped = self.remove_table(old.to_owned())?;
+            Ok((!dropped.as_any().is::<SuccessfulResponse>()).then_some(dropped))
+        };
+
+        let failed = match copy() {
+            Ok(None) => {
+                return Ok(Box::new(SuccessfulResponse {
+                    res: format!("Renamed '{old}' to '{new}'"),
+                    req_uuid: None,
+                }))
+            }
+            Ok(Some(res)) => Ok(res),
+            Err(e) => Err(e),
         };
 
-        Ok(json_response_obj)
+        log::error!("Failed to recreate '{old}' as '{new}', dropping '{new}'");
+        if let Err(e) = self.remove_table(new.to_owned()) {
+            log::error!("Failed to roll back the rename of '{old}': {e:?}");
+        }
+        failed
     }
 }
@@ -1,3 +1,4 @@
+use reqwest::header;
 /// # Api Module: Task Update Operation
 ///
 /// This module provides functionality for updating tasks.
@@ -5,18 +6,20 @@
 /// ## Method
 ///
 /// - `update_task`: Method to update an existing task in a table.
+/// - `complete_task`: Method to mark a task of a table as completed.
+///
+/// Only the fields present in the body are sent, the server keeps the value of the missing ones.
 use std::collections::HashMap;
-use std::io::Read;
+use urlencoding::encode;
 
-use reqwest::{blocking, header};
+use reqwest::StatusCode;
 use serde_json::json;
-use urlencoding::encode;
 
-use crate::api::{ErrorResponse, SuccessfulResponse};
+use crate::api::{parse_response, table_route, task_payload, SuccessfulResponse};
 use crate::error::{Error, Result};
 use crate::utils::table_formatter::FormattedResponse;
 
-use super::{Api, BACKEND};
+use super::Api;
 
 impl Api {
     pub fn update_task(
@@ -25,44 +28,48 @@ impl Api {
         old_desc: String,
         body: HashMap<&str, &str>,
     ) -> Result<Box<dyn FormattedResponse>> {
-        let client = blocking::Client::builder()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| Error::FailedToConnectToServer)?;
-
-        let tablename = match tablename {
-            x if ["reminder", "todo"].contains(&x.as_str()) => x.to_owned(),
+        let client = self.client()?;
 
-            name => format!("user/{}", name),
-        };
+        let tablename = table_route(&tablename);
         let token: String = self.token.clone().unwrap_or_default().into();
         let url_formatted_desc = encode(&old_desc);
-        let url = format!("{}/{}/{}", BACKEND, tablename, url_formatted_desc);
-        let body = json!(body).to_string();
+        let url = format!("{}/{}/{}", self.backend, tablename, url_formatted_desc);
+        let body = task_payload(&body, self.normalize_whitespace)?;
 
-        let mut response = client
-            .put(url)
-            .header(header::COOKIE, token)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(body)
-            .send()
-            .map_err(|_| Error::FailedToConnectToServer)?;
+        let response = self.send(
+            client
+                .put(url)
+                .header(header::COOKIE, token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body),
+        )?;
 
-        let mut body = String::new();
-        response
-            .read_to_string(&mut body)
-            .map_err(|_| Error::InvalidServerResponse)?;
+        parse_response::<SuccessfulResponse>(response)
+    }
+
+    /// Marks a task as completed, a table that can't complete tasks answers the PATCH
+    /// with `405 Method Not Allowed` and gives `Error::CompletionUnsupported`
+    pub fn complete_task(&self, tablename: &str, desc: &str) -> Result<Box<dyn FormattedResponse>> {
+        let client = self.client()?;
+
+        let tablename = table_route(tablename);
+        let token: String = self.token.clone().unwrap_or_default().into();
+        let url = format!("{}/{}/{}", self.backend, tablename, encode(desc));
+        let body = json!({ "completed": true }).to_string();
 
-        let json_response_obj: Box<dyn FormattedResponse> = if body.contains("error") {
-            let err_response: ErrorResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(err_response)
-        } else {
-            let task_response: SuccessfulResponse =
-                serde_json::from_str(&body).map_err(|_| Error::FailedtoReadServerResponse)?;
-            Box::new(task_response)
-        };
+        let response = self.send(
+            client
+                .patch(url)
+                .header(header::COOKIE, token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body),
+        )?;
 
-        Ok(json_response_obj)
+        if [StatusCode::METHOD_NOT_ALLOWED, StatusCode::NOT_IMPLEMENTED]
+            .contains(&response.status())
+        {
+            return Err(Error::CompletionUnsupported);
+        }
+        parse_response::<SuccessfulResponse>(response)
     }
 }
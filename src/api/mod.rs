@@ -3,9 +3,12 @@
 /// This module defines various submodules related to API operations:
 ///
 /// - `api_add`: Module for adding tasks.
+/// - `api_async`: Module for the non-blocking client, only with the `async` feature.
 /// - `api_auth`: Module for authentication.
 /// - `api_clear`: Module for clearing tables.
 /// - `api_list`: Module for listing tables and tasks.
+/// - `api_lookup`: Module for finding the tasks and the tables the commands work on.
+/// - `api_move`: Module for moving tasks between tables.
 /// - `api_remove`: Module for removing tasks.
 /// - `api_tables`: Module for managing tables.
 /// - `api_update`: Module for updating tasks.
@@ -18,7 +21,8 @@
 ///   for API operations.
 ///
 /// - `ErrorResponse`: Struct representing an error response from the API. It contains details
-///   about the error, including a unique request UUID and error type.
+///   about the error, including a unique request UUID and error type, `summary` renders both
+///   on one line.
 ///
 /// - `ErrorDetail`: Struct representing details of an error, including the request UUID and error
 ///   type.
@@ -27,55 +31,414 @@
 ///   the response message.
 ///
 /// - `ErrorType`: Enum representing different types of errors returned by the API. It provides
-///   human-readable error messages corresponding to each error type.
+///   human-readable error messages corresponding to each error type, and a `hint` on what to do.
+///
+/// - `parse_response`: Deserializes a response of the server into either an `ErrorResponse` or
+///   the success type of the endpoint, every api method goes through it.
 ///
 /// ## Constants
 ///
-/// - `BACKEND`: Base URL of the API backend.
+/// - `BACKEND`: Default base URL of the API backend.
+/// - `BACKEND_ENV`: Environment variable (`RSM_BACKEND`) overriding the base URL.
+/// - `DEFAULT_RETRIES`: How many times a rate limited (429) request is retried by default, the
+///   wait follows the `Retry-After` header of the server.
+/// - `DEFAULT_TIMEOUT_SECS`: How long a request may take by default before giving up.
+///
+/// ## Precedence
+///
+/// Explicit flags (`--backend`) take precedence over the environment variables (`RSM_BACKEND`,
+/// `CLI_CLIENT_TOKEN` or `RSM_TOKEN`), which take precedence over the config file (`backend`).
 ///
 /// For detailed information on each submodule, structure, and method, refer to their respective
 /// source files.
 pub mod api_add;
+#[cfg(feature = "async")]
+pub mod api_async;
 pub mod api_auth;
 pub mod api_clear;
 pub mod api_list;
+pub mod api_lookup;
+pub mod api_move;
 pub mod api_remove;
 pub mod api_tables;
 pub mod api_update;
 
 // -- general api utils definitions
+use std::cell::OnceCell;
+use std::env;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use std::collections::HashMap;
+
 use crate::utils::config_helper::Token;
-use crate::{error::Result, utils::config_helper::Config};
-use serde::{Deserialize, Serialize};
+use crate::utils::normalize_description;
+use crate::utils::table_formatter::FormattedResponse;
+use crate::{
+    error::{Error, Result},
+    utils::config_helper::Config,
+};
+use chrono::{DateTime, Utc};
+use reqwest::{blocking, header, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
 
 const BACKEND: &str = "http://100.97.63.15:10001";
+/// The `User-Agent` of the requests, lets the server tell the versions of the client apart
+const USER_AGENT: &str = concat!("cli_client/", env!("CARGO_PKG_VERSION"));
+/// Environment variable overriding the backend url
+pub const BACKEND_ENV: &str = "RSM_BACKEND";
+/// How many times a rate limited request, or a read that failed, is retried when the
+/// config doesn't say
+pub const DEFAULT_RETRIES: u32 = 3;
+/// How long a request may take when the config doesn't say
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// The longest wait before retrying a rate limited request, whatever the server asks
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+/// The wait before the first retry of a read that failed, it doubles at every attempt
+const RETRY_BASE_WAIT: Duration = Duration::from_millis(500);
 
 pub struct Api {
     token: Option<Token>,
+    backend: String,
+    /// Whether the TLS certificates of the backend are not verified
+    insecure: bool,
+    /// Whether the inner whitespace of task descriptions gets collapsed before sending
+    normalize_whitespace: bool,
+    /// How many times a rate limited request, or a read that failed, is retried
+    retries: u32,
+    /// How long a request may take before giving up
+    timeout: Duration,
+    /// Whether the requests are printed instead of being sent
+    dry_run: bool,
+    /// Whether the config never touches the disk, the tables are then not cached either
+    ephemeral: bool,
+    /// The http client shared by all the requests, built on the first one so that it
+    /// keeps the connections and the cookies between them
+    client: OnceCell<blocking::Client>,
 }
 
 impl Api {
-    pub fn new() -> Result<Api> {
-        let token = Config::load_token()?;
-        Ok(Api { token: Some(token) })
+    /// Creates an authenticated api, the token is taken from the config
+    /// (which already accounts for the `CLI_CLIENT_TOKEN` and `RSM_TOKEN` environment variables)
+    pub fn new(config: &Config) -> Result<Api> {
+        let token = Token::from(config.token.clone().ok_or(Error::NoAuth)?);
+        Ok(Api::with_token(Some(token), config))
     }
 
-    pub fn new_without_token() -> Api {
-        Api { token: None }
+    /// Creates an api without authentication, used to log in or sign up
+    pub fn new_without_token(config: &Config) -> Api {
+        Api::with_token(None, config)
     }
 
-    pub fn update_token(&mut self) -> Result<Api> {
-        let token = Config::load_token().map_or(Token::default(), |tok| tok);
-        Ok(Api { token: Some(token) })
+    /// Builds the api from the settings of the config, every setting is read here only
+    fn with_token(token: Option<Token>, config: &Config) -> Api {
+        Api {
+            token,
+            backend: resolve_backend(config.backend.as_deref()),
+            insecure: config.insecure,
+            normalize_whitespace: config.normalize_whitespace,
+            retries: config.retries,
+            timeout: Duration::from_secs(config.timeout_secs),
+            dry_run: false,
+            ephemeral: config.ephemeral,
+            client: OnceCell::new(),
+        }
+    }
+
+    /// Reloads the token from the config file, the client is kept so that the cookies set
+    /// during the auth flow are kept too
+    pub fn update_token(&mut self) {
+        self.token = Some(Config::load_token().unwrap_or_default());
+    }
+
+    /// Overrides the backend url, used for the `--backend` flag
+    pub fn set_backend(&mut self, backend: String) {
+        self.backend = backend.trim_end_matches('/').to_owned();
+    }
+
+    /// Disables the verification of the TLS certificates, meant for self-signed dev servers
+    pub fn set_insecure(&mut self, insecure: bool) {
+        self.insecure = insecure;
+        // the client has to be built again with the new setting
+        self.client = OnceCell::new();
+    }
+
+    /// Prints the requests instead of sending them, used for the `--dry-run` flag
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Returns the http client used for the requests, it is built on the first call,
+    /// `https://` backends are supported
+    fn client(&self) -> Result<&blocking::Client> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+
+        let client = blocking::Client::builder()
+            .use_rustls_tls()
+            .user_agent(USER_AGENT)
+            .cookie_store(true)
+            .danger_accept_invalid_certs(self.insecure)
+            .timeout(self.timeout)
+            .build()
+            .map_err(|_| Error::FailedToConnectToServer)?;
+        Ok(self.client.get_or_init(|| client))
+    }
+
+    /// Sends a request, when the server rate limits it (429) waits as long as its
+    /// `Retry-After` asks (capped at `MAX_RETRY_WAIT`) and retries up to `retries` times.
+    /// In a dry run the request is printed instead and `Error::DryRun` stops the command
+    fn send(&self, mut request: blocking::RequestBuilder) -> Result<blocking::Response> {
+        if self.dry_run {
+            return Err(print_request(request));
+        }
+
+        let mut attempt = 0;
+        loop {
+            // the bodies are plain strings so the request can always be cloned
+            let retry = request.try_clone();
+            // the url has no secret, the token is only in the cookie which isn't logged
+            let target = retry
+                .as_ref()
+                .filter(|_| log::log_enabled!(log::Level::Debug))
+                .and_then(|retry| retry.try_clone()?.build().ok())
+                .map(|request| format!("{} {}", request.method(), request.url()))
+                .unwrap_or_default();
+            log::debug!("Sending {target}");
+            let response = request.send().map_err(request_error)?;
+            log::debug!("{target} answered {}", response.status());
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+            let Some(next) = retry.filter(|_| attempt < self.retries) else {
+                return Err(Error::RateLimited);
+            };
+
+            let wait = retry_after(&response)
+                .unwrap_or(Duration::from_secs(1 << attempt))
+                .min(MAX_RETRY_WAIT);
+            log::info!(
+                "Rate limited by the server, retrying in {}s ({}/{})",
+                wait.as_secs(),
+                attempt + 1,
+                self.retries
+            );
+            thread::sleep(wait);
+
+            attempt += 1;
+            request = next;
+        }
+    }
+
+    /// Sends a read-only request like `send`, it is also retried up to `retries` times when
+    /// the server can't be reached, times out or answers with a 5xx, waiting `RETRY_BASE_WAIT` doubled at
+    /// every attempt plus some jitter. Writes and 4xx answers are never retried this way
+    fn send_idempotent(&self, request: blocking::RequestBuilder) -> Result<blocking::Response> {
+        let mut attempt = 0;
+        loop {
+            // a get request has no body so it can always be cloned
+            let retry = request.try_clone().ok_or(Error::FailedToConnectToServer)?;
+            let reason = match self.send(retry) {
+                Ok(response) if response.status().is_server_error() => {
+                    if attempt >= self.retries {
+                        return Ok(response);
+                    }
+                    format!("the server answered {}", response.status())
+                }
+                Err(Error::FailedToConnectToServer) if attempt < self.retries => {
+                    "the server can't be reached".to_owned()
+                }
+                Err(Error::RequestTimedOut) if attempt < self.retries => {
+                    "the request timed out".to_owned()
+                }
+                res => return res,
+            };
+
+            let wait = backoff(attempt);
+            log::warn!(
+                "Retrying in {}ms since {reason} ({}/{})",
+                wait.as_millis(),
+                attempt + 1,
+                self.retries
+            );
+            thread::sleep(wait);
+            attempt += 1;
+        }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Returns the path of a table in the urls, the built-in tables have their own route while
+/// the ones of the user are under `user/`
+fn table_route(tablename: &str) -> String {
+    match tablename {
+        "reminder" | "todo" => tablename.to_owned(),
+        name => format!("user/{}", urlencoding::encode(name)),
+    }
+}
+
+/// Serializes a task body, normalizing its description before it gets sent
+fn task_payload(body: &HashMap<&str, &str>, normalize_whitespace: bool) -> Result<String> {
+    let mut payload = json!(body);
+    if let Some(desc) = body.get("description") {
+        payload["description"] = json!(normalize_description(desc, normalize_whitespace)?);
+    }
+    Ok(payload.to_string())
+}
+
+/// Turns a request that failed to be sent into the error of its cause
+fn request_error(e: reqwest::Error) -> Error {
+    match e.is_timeout() {
+        true => Error::RequestTimedOut,
+        false => Error::FailedToConnectToServer,
+    }
+}
+
+/// Prints the method, url, headers and body of a request that is not sent, the session
+/// cookie is redacted. Returns the `Error::DryRun` that stops the command
+fn print_request(request: blocking::RequestBuilder) -> Error {
+    let request = match request.build() {
+        Ok(request) => request,
+        Err(e) => {
+            log::error!("Failed to build the request: {e}");
+            return Error::InvalidRequest;
+        }
+    };
+
+    println!("{} {}", request.method(), request.url());
+    println!("{}: {USER_AGENT}", header::USER_AGENT);
+    for (name, value) in request.headers() {
+        let value = match [header::COOKIE, header::AUTHORIZATION].contains(name) {
+            true => "***",
+            false => value.to_str().unwrap_or("<binary>"),
+        };
+        println!("{name}: {value}");
+    }
+    if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+        println!("\n{}", String::from_utf8_lossy(body));
+    }
+    println!();
+
+    Error::DryRun
+}
+
+/// Returns how long to wait before the retry number `attempt` (from 0) of a failed request,
+/// `RETRY_BASE_WAIT` doubled at every attempt plus up to half of it as jitter so that many
+/// clients failing together don't retry together
+fn backoff(attempt: u32) -> Duration {
+    let wait = RETRY_BASE_WAIT * 2u32.saturating_pow(attempt.min(16));
+    // the clock is a good enough source of jitter, no need for a random number generator
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.subsec_nanos());
+    let jitter = wait.mul_f64(f64::from(nanos % 1000) / 2000.0);
+    (wait + jitter).min(MAX_RETRY_WAIT)
+}
+
+/// Reads how long the server asks to wait from the `Retry-After` header, given either
+/// in seconds or as an http date
+fn retry_after(response: &blocking::Response) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    // a date in the past means the request can be retried right away
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Returns the backend url, `RSM_BACKEND` takes precedence over the configured one
+/// which takes precedence over the default one
+fn resolve_backend(configured: Option<&str>) -> String {
+    env::var(BACKEND_ENV)
+        .ok()
+        .or_else(|| configured.map(|backend| backend.to_owned()))
+        .unwrap_or_else(|| BACKEND.to_owned())
+        .trim_end_matches('/')
+        .to_owned()
+}
+
+/// Reads a response of the server and deserializes it, see `parse_body`
+fn parse_response<T>(response: blocking::Response) -> Result<Box<dyn FormattedResponse>>
+where
+    T: DeserializeOwned + FormattedResponse + 'static,
+{
+    let status = response.status();
+    let body = response.text().map_err(|_| Error::InvalidServerResponse)?;
+    parse_body::<T>(status, &body)
+}
+
+/// Deserializes the body of a response into a boxed `T` or `ErrorResponse`, see `parse_typed_body`
+fn parse_body<T>(status: StatusCode, body: &str) -> Result<Box<dyn FormattedResponse>>
+where
+    T: DeserializeOwned + FormattedResponse + 'static,
+{
+    Ok(match parse_typed_body::<T>(status, body)? {
+        Ok(res) => Box::new(res),
+        Err(err_response) => Box::new(err_response),
+    })
+}
+
+/// Deserializes the body of a response, a non-success status is an `ErrorResponse` while
+/// a success one is a `T`, errors sent with a success status are still recognized.
+/// A failing status without an error body becomes the `Error` of its category
+/// (`BadRequest`, `Unauthorized`, `NotFound` or `ServerError`)
+fn parse_typed_body<T>(
+    status: StatusCode,
+    body: &str,
+) -> Result<std::result::Result<T, ErrorResponse>>
+where
+    T: DeserializeOwned,
+{
+    if status.is_success() {
+        if let Ok(res) = serde_json::from_str::<T>(body) {
+            return Ok(Ok(res));
+        }
+    }
+
+    // the status alone decides which body is expected, the description of a task may
+    // contain anything (e.g. "error in prod") so the body text is never looked into
+    match serde_json::from_str::<ErrorResponse>(body) {
+        Ok(err_response) => {
+            if status.is_success() {
+                log::info!("The server sent an error with a {status} status");
+            }
+            Ok(Err(err_response))
+        }
+        // without an error body the status tells what went wrong, e.g. a route that
+        // doesn't exist (a wrong table or task) or a proxy in front of the server
+        Err(_) => Err(match status {
+            StatusCode::BAD_REQUEST => Error::BadRequest(match body.trim() {
+                "" => status.canonical_reason().unwrap_or_default().to_owned(),
+                detail => detail.to_owned(),
+            }),
+            StatusCode::UNAUTHORIZED => Error::Unauthorized,
+            StatusCode::NOT_FOUND => Error::NotFound,
+            status if status.is_server_error() => Error::ServerError(status.as_u16()),
+            _ => Error::FailedtoReadServerResponse,
+        }),
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ErrorResponse {
     pub error: ErrorDetail,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ErrorDetail {
     pub req_uuid: String,
     #[serde(rename = "type")]
@@ -85,9 +448,43 @@ pub struct ErrorDetail {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SuccessfulResponse {
     pub res: String,
+    /// The id of the request, only some servers send it on success
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub req_uuid: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl ErrorResponse {
+    /// Returns a one line summary with the request id, e.g.
+    /// `Error: NO_AUTH (request 0b3f...)`, the id lets the server logs be searched
+    pub fn summary(&self) -> String {
+        format!(
+            "Error: {:?} (request {})",
+            self.error.error_type, self.error.req_uuid
+        )
+    }
+}
+
+/// An answer of the server that is an error, for the callers that need it as an `Error`
+impl From<ErrorResponse> for Error {
+    fn from(err: ErrorResponse) -> Error {
+        Error::ApiError {
+            error_type: err.error.error_type,
+            req_uuid: err.error.req_uuid,
+        }
+    }
+}
+
+impl SuccessfulResponse {
+    /// Returns the first value between single quotes in the message, the server quotes
+    /// the relevant values it sends back (e.g. the key after a signup)
+    pub fn quoted_value(&self) -> Option<&str> {
+        let start = self.res.find('\'')? + 1;
+        let end = start + self.res[start..].find('\'')?;
+        Some(&self.res[start..end])
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[allow(non_camel_case_types)]
 pub enum ErrorType {
     LOGIN_FAIL,
@@ -116,3 +513,21 @@ impl std::fmt::Display for ErrorType {
         write!(f, "{:<29}", error_message)
     }
 }
+
+impl ErrorType {
+    /// A sentence telling the user what went wrong and what to do about it
+    pub fn hint(&self) -> &'static str {
+        match self {
+            ErrorType::LOGIN_FAIL => "The username, the password or the key is wrong",
+            ErrorType::USER_NOT_FOUND => "No account with this username exists",
+            ErrorType::USERNAME_ALREADY_USED => "Someone already has this username, pick another",
+            ErrorType::TABLENAME_ALREADY_USED => "A table with this name already exists",
+            ErrorType::NO_AUTH => "The session expired or is missing, log in again",
+            ErrorType::INVALID_PARAMS | ErrorType::INVALID_QUERY_PARAMS => {
+                "The server rejected the arguments, check the table, the task and the options"
+            }
+            ErrorType::DUE_UNSUPPORTED => "This table doesn't support dues",
+            ErrorType::SERVICE_ERROR => "The server failed, try again later",
+        }
+    }
+}
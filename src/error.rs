@@ -1,4 +1,7 @@
 use serde::Serialize;
+use serde_json::json;
+
+use crate::api::ErrorType;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -8,20 +11,236 @@ pub enum Error {
     FailedToReadConfig,
     InvalidConfig,
     FailedToUpdateConf,
+    NoDefaultTable,
+    InvalidConfigValue,
+    InvalidTimezone,
+    FailedToUpdateOpLog,
+    FailedToUpdateNotifiedLog,
+    ProfileNotFound,
+    ProfileAlreadyExists,
 
     // -- Server errors
     FailedToConnectToServer,
     FailedtoReadServerResponse,
     InvalidServerResponse,
+    RateLimited,
+    RequestTimedOut,
+    InvalidRequest,
+    BadRequest(String),
+    Unauthorized,
+    NotFound,
+    ServerError(u16),
+    ApiError {
+        error_type: ErrorType,
+        req_uuid: String,
+    },
 
     // -- Other errors
+    DryRun,
     RsmFailed, // basically status code 500
     FirstRunFailed,
     FailedToUpdateKey,
-    FailedToResolveFile { detail: String },
+    FailedToResolveFile {
+        detail: String,
+    },
+    FailedToWriteOutput {
+        detail: String,
+    },
     InvalidDate,
+    PastDue,
+    InvalidDueWindow,
+    FailedToNotify,
+
+    // -- Task errors
+    FailedToFetchTasks,
+    TaskNotFound,
+    AmbiguousTask {
+        name: String,
+        matches: Vec<String>,
+    },
+    EmptyTaskName,
+    BuiltinTable,
+    TableDoesNotSupportDue,
+    TableNotFound,
+    InvalidTableName,
+    CompletionUnsupported,
+    NothingToUndo,
+    FailedToUndo,
 
     // -- Auth errors
     NoAuth,
     LoginFail,
+    NoPassword,
+    NoTerminal,
+}
+
+impl Error {
+    /// Returns the machine readable representation of the error, server errors keep
+    /// the type and request uuid sent by the server
+    pub fn to_json(&self) -> serde_json::Value {
+        let (error_type, req_uuid) = match self {
+            Error::ApiError {
+                error_type,
+                req_uuid,
+            } => (json!(error_type), Some(req_uuid.as_str())),
+            _ => (json!(self.as_ref()), None),
+        };
+
+        json!({
+            "error": {
+                "type": error_type,
+                "req_uuid": req_uuid,
+                "message": self.to_string(),
+            }
+        })
+    }
+}
+
+impl Error {
+    /// Returns the exit code of the process for the category of the error: 2 for a bad
+    /// input, 3 for a failed authentication, 4 for a server that failed and 1 otherwise
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::BadRequest(_)
+            | Error::InvalidRequest
+            | Error::NotFound
+            | Error::NoDefaultTable
+            | Error::InvalidConfigValue
+            | Error::InvalidTimezone
+            | Error::ProfileNotFound
+            | Error::ProfileAlreadyExists
+            | Error::InvalidDate
+            | Error::PastDue
+            | Error::InvalidDueWindow
+            | Error::FailedToResolveFile { .. }
+            | Error::TaskNotFound
+            | Error::AmbiguousTask { .. }
+            | Error::EmptyTaskName
+            | Error::BuiltinTable
+            | Error::TableDoesNotSupportDue
+            | Error::TableNotFound
+            | Error::InvalidTableName
+            | Error::CompletionUnsupported
+            | Error::NoTerminal => 2,
+            Error::Unauthorized | Error::NoAuth | Error::LoginFail | Error::NoPassword => 3,
+            Error::ServerError(_)
+            | Error::FailedToConnectToServer
+            | Error::FailedtoReadServerResponse
+            | Error::InvalidServerResponse
+            | Error::RateLimited
+            | Error::RequestTimedOut => 4,
+            Error::ApiError { error_type, .. } => match error_type {
+                ErrorType::INVALID_PARAMS
+                | ErrorType::INVALID_QUERY_PARAMS
+                | ErrorType::DUE_UNSUPPORTED
+                | ErrorType::TABLENAME_ALREADY_USED
+                | ErrorType::USERNAME_ALREADY_USED => 2,
+                ErrorType::NO_AUTH | ErrorType::LOGIN_FAIL | ErrorType::USER_NOT_FOUND => 3,
+                ErrorType::SERVICE_ERROR => 4,
+            },
+            _ => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::FailedToReadConfig => write!(f, "Failed to read the config file"),
+            Error::InvalidConfig => write!(f, "The config file is invalid"),
+            Error::FailedToUpdateConf => write!(f, "Failed to update the config file"),
+            Error::NoDefaultTable => write!(f, "No table specified and no default table set"),
+            Error::InvalidConfigValue => write!(f, "Invalid value for the config key"),
+            Error::InvalidTimezone => {
+                write!(f, "Unknown timezone, use an IANA name like Europe/Rome")
+            }
+            Error::FailedToUpdateOpLog => write!(f, "Failed to update the operation log"),
+            Error::FailedToUpdateNotifiedLog => {
+                write!(f, "Failed to update the log of the notified tasks")
+            }
+            Error::ProfileNotFound => {
+                write!(f, "No profile with this name, see `rsm profile list`")
+            }
+            Error::ProfileAlreadyExists => write!(f, "A profile with this name already exists"),
+            Error::FailedToConnectToServer => write!(f, "Failed to connect to the server"),
+            Error::FailedtoReadServerResponse => write!(f, "Failed to read the server response"),
+            Error::InvalidServerResponse => write!(f, "The server sent an invalid response"),
+            Error::BadRequest(detail) => {
+                write!(f, "The server rejected the request (400): {detail}")
+            }
+            Error::Unauthorized => write!(f, "The server refused the session (401), log in again"),
+            Error::NotFound => write!(
+                f,
+                "Not found on the server (404), check the table and the task"
+            ),
+            Error::ServerError(status) => write!(f, "The server failed to answer ({status})"),
+            Error::RateLimited => write!(f, "The server keeps rate limiting the requests"),
+            Error::RequestTimedOut => write!(f, "The server took too long to answer"),
+            Error::InvalidRequest => write!(f, "The request could not be built"),
+            Error::ApiError {
+                error_type,
+                req_uuid,
+            } => write!(f, "{} (request {req_uuid})", error_type.hint()),
+            Error::DryRun => write!(f, "Dry run, the request was not sent"),
+            Error::RsmFailed => write!(f, "Something went wrong"),
+            Error::FirstRunFailed => write!(f, "Failed to create the account"),
+            Error::FailedToUpdateKey => write!(f, "Failed to update the key"),
+            Error::FailedToResolveFile { detail } => write!(f, "Failed to read the file: {detail}"),
+            Error::FailedToWriteOutput { detail } => {
+                write!(f, "Failed to write the output file: {detail}")
+            }
+            Error::FailedToNotify => write!(f, "Failed to send the notification"),
+            Error::InvalidDate => write!(f, "Invalid date"),
+            Error::PastDue => write!(f, "The due is in the past, drop --no-past-due to accept it"),
+            Error::InvalidDueWindow => write!(
+                f,
+                "The due window ends before it starts, --until/--due-before has to be after --since/--due-after"
+            ),
+            Error::FailedToFetchTasks => write!(f, "Failed to fetch the tasks"),
+            Error::TaskNotFound => write!(f, "Task not found"),
+            Error::AmbiguousTask { name, matches } => {
+                write!(
+                    f,
+                    "More tasks match '{name}', pass the full description of one of them:"
+                )?;
+                matches
+                    .iter()
+                    .try_for_each(|task| write!(f, "\n  - {task}"))
+            }
+            Error::EmptyTaskName => write!(f, "The task description is empty"),
+            Error::BuiltinTable => {
+                write!(
+                    f,
+                    "The reminder and todo tables can't be dropped or renamed"
+                )
+            }
+            Error::InvalidTableName => write!(
+                f,
+                "Invalid table name, it can't be empty, contain '/' or be reminder or todo"
+            ),
+            Error::TableNotFound => write!(f, "No table with this name, see `rsm list`"),
+            Error::TableDoesNotSupportDue => write!(
+                f,
+                "The table doesn't support dues, drop the due or use a table created with --due"
+            ),
+            Error::CompletionUnsupported => {
+                write!(f, "The tasks of this table can't be marked as completed")
+            }
+            Error::NothingToUndo => write!(f, "Nothing to undo"),
+            Error::FailedToUndo => write!(f, "Failed to undo the operation"),
+            Error::NoAuth => write!(
+                f,
+                "Not logged in or the session expired without a saved key, log in again"
+            ),
+            Error::LoginFail => write!(f, "Login failed"),
+            Error::NoPassword => write!(
+                f,
+                "No terminal to ask the password on, set RSM_PASSWORD for non-interactive use"
+            ),
+            Error::NoTerminal => write!(
+                f,
+                "This needs a terminal, use the plain commands (e.g. add --task) instead"
+            ),
+        }
+    }
 }
@@ -0,0 +1,61 @@
+//! # rsm
+//!
+//! The library behind the `rsm` cli, it can be used to manage the todos, the reminders and
+//! the custom tables from another application, without going through the command line.
+//!
+//! ## Usage
+//!
+//! An `Api` is built from a `Config`, loaded from the config file or from `CLI_CLIENT_TOKEN`:
+//!
+//! ```no_run
+//! use std::collections::HashMap;
+//!
+//! use rsm::{Api, Config, TaskListing};
+//!
+//! fn main() -> rsm::Result<()> {
+//!     let config = Config::get_config()?;
+//!     let api = Api::new(&config)?;
+//!     if let TaskListing::Tasks(tasks) = api.get_tasks(Some("todo"), HashMap::new())? {
+//!         for task in tasks.res {
+//!             println!("{}", task.description);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ## Modules
+//!
+//! - `api`: The blocking client and the types of the server responses (and, with the `async`
+//!   feature, the async client).
+//! - `error`: The error type shared by the library and the cli.
+//! - `parsers`: The parsers of the user input (dues, recurrences, queries, ...).
+//! - `tui`: The interactive terminal interface, only with the `cli` feature.
+//! - `utils`: The config, the op log, the timezone and the other helpers shared with the cli.
+//!
+//! The most used types are re-exported at the crate root.
+//!
+//! ## Features
+//!
+//! - `cli` (default): The `rsm` binary and what only it needs, the terminal interface, the
+//!   output of the commands, the prompts and the notifications. Applications embedding the
+//!   library turn the default features off.
+//! - `async`: `AsyncApi`, a non-blocking client.
+pub mod api;
+pub mod error;
+pub mod parsers;
+#[cfg(feature = "cli")]
+pub mod tui;
+pub mod utils;
+
+#[cfg(feature = "async")]
+pub use api::api_async::AsyncApi;
+pub use api::api_list::{
+    GetTaskResponse, GetTaskResponseDetail, TableCharacteristicsResponse,
+    TableCharacteristicsResponseDetails, TaskListing,
+};
+pub use api::{Api, ErrorResponse, SuccessfulResponse};
+pub use error::{Error, Result};
+pub use parsers::Due;
+pub use utils::config_helper::Config;
+pub use utils::table_formatter::FormattedResponse;
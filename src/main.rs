@@ -9,65 +9,261 @@
 //! The CLI app provides various subcommands to perform different operations. Here are the available
 //! subcommands:
 //!
-//! - `new-key`: Resets the account key.
+//! - `new-key` (`recover`): Resets the account key.
 //! - `logout`: Logs out from the account.
-//! - `list`: Lists tables with specifications or table contents. It supports options like filtering
-//!   by table name, specifying a group, and sorting the output.
-//! - `create`: Creates a new table.
+//! - `whoami`: Shows the account logged in and when the session expires.
+//! - `list` (`ls`): Lists tables with specifications or table contents. It supports options like
+//!   filtering by table name, specifying a group, and sorting the output.
+//! - `create` (`mk`): Creates a new table.
 //! - `drop`: Deletes a table.
-//! - `add`: Adds a task into a table. It supports adding tasks from text input or file input with
-//!   options like specifying due date, group, etc.
-//! - `remove`: Removes a task from a table.
+//! - `rename`: Renames a table, keeping its tasks.
+//! - `add` (`new`): Adds a task into a table. It supports adding tasks from text input or file input
+//!   with options like specifying due date, group, etc.
+//! - `remove` (`rm`): Removes tasks from a table.
 //! - `update`: Updates a task from a table. It supports updating task description, due date, group,
 //!   etc.
+//! - `duplicate`: Duplicates a task of a table.
+//! - `move` (`mv`): Moves a task to another group or table.
+//! - `done`: Marks tasks as completed.
+//! - `config`: Manages the local configuration.
+//! - `profile`: Manages the accounts, each with its own key, session and backend.
 //! - `clear`: Clears completely a table.
+//! - `show`: Shows every field of a task.
+//! - `search` (`find`): Searches the tasks whose description contains a text.
+//! - `stats`: Counts the tasks of a table, in total, overdue, due today and by group.
+//! - `notify`: Sends a desktop notification for every overdue task.
+//! - `undo`: Reverts the last add, update, move, remove, clear or drop.
+//! - `completions`: Prints the completion script of a shell.
+//! - `tui`: Opens the terminal interface, the tables in a sidebar and the tasks of the selected
+//!   one in the main pane (`a` adds, `d` removes, `q` quits).
+//!
+//! Without a subcommand `rsm` opens the `frontend` set in the config: the help for `cli` (the
+//! default) or the terminal interface for `tui`.
+//!
+//! ## Global Options
+//!
+//! - `json`: Print the results as compact json on stdout, one document per result, and the
+//!   errors as a json object (`{"error":{"type":...,"req_uuid":...,"message":...}}`) on stderr
+//!   instead of the human readable output.
+//! - `backend`: The url of the server, overrides `RSM_BACKEND` and the config for this run.
+//! - `insecure`: Skip the TLS certificate verification, only for self-signed dev servers.
+//! - `verbose` (`-v`): Log on stderr too (stdout stays clean for the results), `-v` logs the
+//!   `info` level and `-vv` the `debug` one, which also has the method and url of every request
+//!   and the status of its response. `RUST_LOG` set to a level (e.g. `RUST_LOG=debug`) wins over
+//!   the flag. The log file always gets the `info` level.
+//! - `dry-run`: Print the method, url, headers and body of the request instead of sending it,
+//!   the session cookie is shown as `***`. The command stops at the first request whose
+//!   answer it needs to go on.
+//! - `due-timezone`: The IANA timezone (e.g. `Europe/Rome`) the dues are typed and shown in for
+//!   this run, overrides the `timezone` of the config, defaults to the system one. The dues are
+//!   stored in UTC, a time skipped or repeated by a daylight saving change is refused. The
+//!   zone is shown next to the dates.
+//! - `print-request-id`: Print the id the server gives to the request as a last
+//!   `(server request id: ...)` line on stderr even when it succeeds, if the server sends one.
+//!   A request the server fails always gets it, so that the id can be pasted in a bug report.
+//! - `quiet`: Don't print anything on stdout for the commands that succeed, the errors are still
+//!   printed on stderr (the answers of the server with an error too) and the exit code tells
+//!   the outcome. It wins over `json`, but not over `output`.
+//! - `output`: Write the result to a file instead of stdout, creating its parent directories.
+//! - `no-color`: Don't color the output, the colors are also off when `NO_COLOR` is set or
+//!   stdout is not a terminal. Overdue tasks are red and the ones due within an hour yellow.
+//!
+//! ## Exit Codes
+//!
+//! - `0`: The command succeeded.
+//! - `1`: Any other failure.
+//! - `2`: Bad input, e.g. invalid arguments, a task that doesn't exist or a request the server
+//!   rejected (400 or 404).
+//! - `3`: Authentication failed, e.g. not logged in or the session was refused (401).
+//! - `4`: The server failed, it can't be reached, timed out or answered with a 5xx.
 //!
 //! ## Subcommands and Arguments
 //!
 //! Each subcommand has its own set of arguments and options. Below are the details of each subcommand
 //! and their corresponding arguments:
 //!
-//! - `new-key`: No arguments.
+//! - `new-key`:
+//!     - `username`: The username of the account, asked interactively if omitted.
 //!
 //! - `logout`: No arguments.
 //!
+//! - `whoami`: No arguments, shows the username (when known) and when the session expires.
+//!
 //! - `list`:
 //!     - `tablename`: Name of the table to show (optional).
 //!     - `group`: Specify the group to show (requires `tablename`).
-//!     - `sort-by`: The key to sort the output by (requires `tablename`).
+//!     - `sort-by`: The key to sort the output by, `due`, `group` or `description` with an optional
+//!       `:asc`/`:desc` direction, the tasks without a due go last (requires `tablename`).
+//!     - `since` (`due-after`): Only show tasks due from this moment (requires `tablename`).
+//!     - `until` (`due-before`): Only show tasks due up to this moment (requires `tablename`),
+//!       a window ending before it starts is refused before fetching anything.
+//!     - `created-after`: Only show tasks created after this moment (requires `tablename`).
+//!     - `limit`: The maximum number of tasks to fetch (requires `tablename`).
+//!     - `page`: The page of tasks to show, from 1, the footer then shows which tasks of the
+//!       table these are, e.g. `showing 51–100 of 130` (requires `tablename`, conflicts with
+//!       `limit`).
+//!     - `page-size`: How many tasks a page has, defaults to 50 (requires `tablename`, conflicts
+//!       with `limit`).
+//!     - `query`: Additional `KEY=VALUE` query parameter forwarded to the server, can be repeated
+//!       (requires `tablename`).
+//!     - `counts`: Show how many tasks each table has (conflicts with `tablename`).
+//!     - `format`: How to render the tasks, `table` (default), `markdown` or `csv` (requires
+//!       `tablename`).
+//!     - `checklist`: Render the tasks as a markdown `- [ ]` checklist ticking the completed ones,
+//!       implies `--format markdown` (requires `tablename`).
+//!     - `overdue`: Only show the tasks past their due, the most overdue first unless `sort-by`
+//!       is given, it can be combined with the other filters (requires `tablename`).
+//!     - `absolute-dates`: Show the dues in the table as dates instead of relative to now, e.g.
+//!       `in 1h 30m` or `5m ago` (requires `tablename`).
 //!
 //! - `create`:
-//!     - `tablename`: Name of the table to create (required).
+//!     - `tablename`: Name of the table to create (required), it can't be empty, contain `/`
+//!       or be `reminder` or `todo`.
 //!     - `due`: Set if the table has a due time, defaults to false.
+//!     - `no-group`: Set if the tasks of the table have no group, by default they have one.
 //!
 //! - `drop`:
 //!     - `tablename`: Name of the table to remove (required).
+//!     - `yes`: Skip the confirmation, it is also skipped when stdin is not a terminal.
+//!
+//! - `rename`:
+//!     - `old`: The name of the table to rename (required), `reminder` and `todo` can't be.
+//!     - `new`: The new name of the table (required), it can't be empty, contain `/` or be
+//!       `reminder` or `todo`. When the server can't rename tables, the table is recreated with
+//!       the new name and its tasks are copied before the old one is dropped.
 //!
 //! - `add`:
-//!     - `tablename`: Name of the table where to add the task (required).
+//!     - `tablename`: Name of the table where to add the task (defaults to `default_table`).
 //!     - `task`: The task to add as text (conflicts with `file`).
 //!     - `file`: File from where to find the description of the task to add (conflicts with `task`).
+//!     - `interactive`: Ask the description, the due (shown back as a date to confirm) and the
+//!       group one by one, needs a terminal (conflicts with `task`, `file`, `from`, `due` and
+//!       `group`).
+//!     - `from`: Add a task for every line of a file (`-` for stdin) in the format
+//!       `<name> | <due> | <group>`, the due and the group can be left empty. A summary of the
+//!       lines added and failed is printed at the end (conflicts with the other task options).
 //!     - `line`: Add task from a specific line (requires `file`).
 //!     - `range`: Add task from a range (requires `file`).
-//!     - `due`: The due of the task in one of the formats: 'hh:mm' or 'YYYY-MM-dd hh:mm'.
+//!     - `due`: The due of the task in one of the formats: 'hh:mm', 'YYYY-MM-dd hh:mm',
+//!       'YYYY-MM-ddThh:mm', '<today|tomorrow|weekday> hh:mm' or '+<N><m|h|d>', the times can
+//!       have seconds ('hh:mm:ss'). A due on a table without due fails before sending, see
+//!       the table cache below.
 //!     - `group`: The group of the task.
+//!     - `note`: A longer note attached to the task (alias `attach-note`).
+//!     - `every`: How often the task repeats (`daily`, `weekly`, `2d`, `1w`, ...), ignored on
+//!       tables without due.
+//!     - `no-past-due`: Refuse a due already past (e.g. `2020-01-01 09:00`) instead of only
+//!       logging a warning.
 //!
 //! - `remove`:
-//!     - `tablename`: Name of the table where to remove the task (required).
-//!     - `desc`: The description of the task to remove (required).
+//!     - `tablename`: Name of the table where to remove the task (defaults to `default_table`).
+//!     - `desc`: The descriptions of the tasks to remove (required unless `name` is given). With
+//!       more than one the tasks are removed as a batch: a task that can't be removed doesn't
+//!       stop the others and a summary of the outcomes is printed, the ones removed are undone
+//!       together.
+//!     - `name`: Remove the only task whose description contains this text, the positional
+//!       becomes the table.
+//!     - `yes`: Skip the confirmation when removing by name (requires `name`).
 //!
 //! - `update`:
-//!     - `tablename`: Name of the table where to update the task (required).
+//!     - `tablename`: Name of the table where to update the task (defaults to `default_table`).
 //!     - `desc`: The description of the task to update (required).
 //!     - `task`: The new description of the task as text (conflicts with `file`).
 //!     - `file`: The new description of the task from a file (conflicts with `task`).
+//!     - At least one of `task`, `file`, `due`, `group`, `note` and `every` is required, the
+//!       fields that are not given keep their value.
 //!     - `line`: Add task from a specific line (requires `file`).
 //!     - `range`: Add task from a range (requires `file`).
-//!     - `due`: The due of the task in one of the formats: 'hh:mm' or 'YYYY-MM-dd hh:mm'.
+//!     - `due`: The due of the task in one of the formats: 'hh:mm', 'YYYY-MM-dd hh:mm',
+//!       'YYYY-MM-ddThh:mm', '<today|tomorrow|weekday> hh:mm' or '+<N><m|h|d>', the times can
+//!       have seconds ('hh:mm:ss').
 //!     - `group`: The group of the task.
+//!     - `note`: A longer note attached to the task (alias `attach-note`).
+//!     - `every`: How often the task repeats (`daily`, `weekly`, `2d`, `1w`, ...), ignored on
+//!       tables without due.
+//!     - `no-past-due`: Refuse a due already past (e.g. `2020-01-01 09:00`) instead of only
+//!       logging a warning.
+//!
+//! - `duplicate`:
+//!     - `tablename`: Name of the table where to duplicate the task (defaults to `default_table`).
+//!     - `desc`: The description of the task to duplicate (required).
+//!     - `task`: The description of the copy, defaults to the original one.
+//!
+//! - `move`:
+//!     - `tablename`: Name of the table of the task (defaults to `default_table`).
+//!     - `desc`: The description of the task to move (required).
+//!     - `to-group`: The group to move the task to.
+//!     - `to-table`: The table to move the task to, the task is added there and removed from
+//!       `tablename`. A table that doesn't exist fails before anything is moved.
+//!     - At least one of `to-group` and `to-table` is required.
+//!
+//! - `done` (alias `complete`):
+//!     - `tablename`: Name of the table of the tasks (defaults to `default_table`).
+//!     - `desc`: The descriptions of the tasks to complete, one or more (required). A task
+//!       that repeats is moved to its next due instead.
+//!
+//! - `config`:
+//!     - `set`: Sets a configuration value, the supported keys are:
+//!         - `default_table`: The table used when a command is ran without a tablename.
+//!         - `normalize_whitespace`: Whether to collapse the whitespace of task descriptions
+//!           (`true` or `false`, defaults to `true`).
+//!         - `timezone`: The IANA timezone dues are typed and shown in (e.g. `Europe/Rome`),
+//!           defaults to the system one, `--due-timezone` wins over it.
+//!         - `backend`: The url of the server of the active profile, defaults to the built-in
+//!           one.
+//!         - `insecure`: Whether to skip the TLS certificate verification (`true` or `false`,
+//!           defaults to `false`).
+//!         - `retries`: How many times a rate limited request, or a listing the server failed to
+//!           answer (unreachable, timed out or 5xx), is retried, defaults to 3.
+//!         - `timeout_secs`: How long a request may take before giving up, defaults to 30.
+//!         - `frontend`: What `rsm` opens without a subcommand, `cli` shows the help and `tui`
+//!           opens the terminal interface, defaults to `cli`.
+//!     - `check`: Checks the config file as it is on disk (it isn't loaded, so a broken one
+//!       isn't reset): that it parses, that the backends are http(s) urls, that the timezone
+//!       exists and that the session has a readable expiry not yet past. It prints what it
+//!       found and the problems, and fails with the exit code of an invalid config if any.
+//!
+//! - `profile`:
+//!     - `add`: Adds an account named `name`, with its own `backend` (optional), it is logged
+//!       into the first time it is used.
+//!     - `use`: Switches to the account named `name`, the other accounts stay logged in.
+//!     - `list`: Lists the accounts, marking the one in use.
 //!
 //! - `clear`:
-//!     - `tablename`: Name of the table where to clear (required).
+//!     - `tablename`: Name of the table where to clear (defaults to `default_table`).
+//!     - `completed`: Only remove the completed tasks.
+//!     - `yes`: Skip the confirmation, it is also skipped when stdin is not a terminal.
+//!
+//! - `show`:
+//!     - `tablename`: Name of the table of the task (defaults to `default_table`).
+//!     - `desc`: The description of the task, or a part of it matching only one task (required).
+//!
+//! - `search`:
+//!     - `query`: The text to search in the descriptions, case insensitive (required).
+//!     - `table`: Only search this table, otherwise every table is searched and the results are
+//!       grouped by table.
+//!
+//! - `stats`:
+//!     - `tablename`: Name of the table to count (defaults to `default_table`), the total, the
+//!       overdue tasks, the ones due later today and the tasks of every group are shown.
+//!
+//! - `notify`:
+//!     - `window`: Don't notify again a task notified in the last `window` minutes, defaults
+//!       to 60. The last notifications are kept in `notified.json` next to the config file.
+//!
+//! - `undo`: No arguments, the last operations are kept in `oplog.json` next to the config
+//!   file.
+//!
+//! - `completions`:
+//!     - `shell`: The shell to print the completion script of, `bash`, `zsh`, `fish`,
+//!       `powershell` or `elvish` (required), e.g. `rsm completions bash > /etc/bash_completion.d/rsm`.
+//!
+//! ## Table Cache
+//!
+//! The tables and whether they have a due are cached in `tables.json` next to the config file,
+//! so that the checks before a request (e.g. a due on a table without due) don't need the server.
+//! The cache is refreshed every time the tables are listed (`list` without a table) and when
+//! it is older than an hour, and dropped after `create` and `drop`.
 //!
 //! ## Main Function
 //!
@@ -76,11 +272,13 @@
 //!
 //! ## Modules
 //!
-//! The `main.rs` file includes several modules:
+//! The `main.rs` file is only the command line shell, the work is done by the `rsm` library
+//! (`lib.rs`) and its modules:
 //!
 //! - `api`: Contains API-related functionalities.
 //! - `error`: Defines custom error types and handling.
 //! - `parsers`: Provides parsers for parsing input data.
+//! - `tui`: Contains the interactive terminal interface.
 //! - `utils`: Includes utility functions for configuration management, user interaction, etc.
 //!
 //! ## Dependencies
@@ -88,6 +286,7 @@
 //! The CLI app uses several external crates:
 //!
 //! - `clap`: For parsing command-line arguments.
+//! - `clap_complete`: For generating the shell completions.
 //! - `dotenv`: For loading environment variables from a .env file.
 //! - `log4rs`: For logging configuration and management.
 //!
@@ -103,39 +302,147 @@
 //!
 //! For further details on specific functions and implementations, refer to the comments and code
 //! in the `main.rs` file.
-use std::io::Write;
-use std::{collections::HashMap, path::PathBuf};
-use std::{env, io};
+use std::io::{IsTerminal, Write};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use std::{env, io, process::ExitCode};
 
+use chrono::{Duration, Utc};
 use clap::{command, value_parser, Arg, ArgAction, ArgGroup, Command};
+use clap_complete::{generate, Shell};
+use log4rs::append::console::{ConsoleAppender, Target};
 use log4rs::append::file::FileAppender;
 use log4rs::config::{Appender, Logger, Root};
 use log4rs::encode::pattern::PatternEncoder;
-use parsers::LineRange;
-use utils::config_helper::{Config, Token};
-use utils::find_log_path;
-
-use crate::api::{ErrorResponse, SuccessfulResponse};
-use crate::error::Result;
-use crate::parsers::Due;
-use crate::utils::{get_user_choice, resolve_file_input, Choice};
-use crate::{api::Api, error::Error};
-
-pub mod api;
-pub mod error;
-pub mod parsers;
-pub mod utils;
-
-/// Return the clalp arg matcher for the cli input
-fn app_args() -> clap::ArgMatches {
+use log4rs::filter::threshold::ThresholdFilter;
+use rsm::parsers::{LineRange, QueryParam, Recurrence, SortKey};
+use rsm::utils::config_helper::{check_config_file, Config, Frontend, Profile, Token};
+use rsm::utils::find_log_path;
+
+use rsm::api::api_list::{
+    check_due_window, GetTaskResponseDetail, TableCharacteristicsResponse, TaskListing,
+};
+use rsm::api::{ErrorResponse, SuccessfulResponse};
+use rsm::error::Result;
+use rsm::parsers::Due;
+use rsm::utils::batch_report::BatchReport;
+use rsm::utils::notifications::{send_overdue_notification, NotifiedLog};
+use rsm::utils::op_log::{record_op, Op, OpLog};
+use rsm::utils::output::{
+    emit, flush_output, print_response, set_color, set_output_file, set_print_request_id,
+    set_quiet, set_spinner, OutputMode,
+};
+use rsm::utils::table_formatter::FormattedResponse;
+use rsm::utils::timezone::{server_now, set_zone, Zone};
+use rsm::utils::wizard::add_wizard;
+use rsm::utils::{
+    confirm, get_user_choice, normalize_description, resolve_file_input, validate_tablename,
+    with_spinner, Choice,
+};
+use rsm::{api::Api, error::Error};
+
+/// Return the clap command tree of the cli, shared by the argument parsing and the
+/// shell completions
+fn app_command() -> Command {
     command!()
-        .subcommand_required(true)
-        .arg_required_else_help(true)
-        .subcommand(Command::new("new-key").about("Resets the account key"))
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print the results as compact json and the errors as a json object on stderr"),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .global(true)
+                .value_name("FILE")
+                .help("Write the result to this file instead of stdout")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("backend")
+                .long("backend")
+                .global(true)
+                .value_name("URL")
+                .help("The url of the server, overrides RSM_BACKEND and the config for this run"),
+        )
+        .arg(
+            Arg::new("no-color")
+                .long("no-color")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Don't color the output, also off with NO_COLOR set or when stdout is not a terminal"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .short('v')
+                .global(true)
+                .action(ArgAction::Count)
+                .help("Log on stderr too, -v for the info level and -vv for the debug one with every request"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print the request that would be sent instead of sending it"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .long("quiet")
+                .short('q')
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Don't print the results of the commands that succeed, only the errors on stderr"),
+        )
+        .arg(
+            Arg::new("due-timezone")
+                .long("due-timezone")
+                .global(true)
+                .value_name("TZ")
+                .value_parser(|tz: &str| tz.parse::<Zone>().map_err(|e| e.to_string()))
+                .help("The IANA timezone the dues are typed and shown in (e.g. Europe/Rome), overrides the config"),
+        )
+        .arg(
+            Arg::new("print-request-id")
+                .long("print-request-id")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print the id the server gives to the request on stderr even when it succeeds"),
+        )
+        .arg(
+            Arg::new("insecure")
+                .long("insecure")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Skip the TLS certificate verification, only for self-signed dev servers"),
+        )
+        .subcommand(
+            Command::new("new-key")
+                .about("Resets the account key")
+                .visible_alias("recover")
+                .arg(
+                    Arg::new("username")
+                        .long("username")
+                        .short('u')
+                        .help("The username of the account, asked interactively if omitted"),
+                ),
+        )
         .subcommand(Command::new("logout").about("Logout from the account"))
+        .subcommand(
+            Command::new("whoami")
+                .about("Shows the account logged in and when the session expires"),
+        )
         .subcommand(
             Command::new("list")
                 .about("List tables with specs or table contents")
+                .visible_alias("ls")
                 .arg(
                     Arg::new("tablename")
                         .required(false)
@@ -153,12 +460,108 @@ fn app_args() -> clap::ArgMatches {
                         .short('s')
                         .long("sort-by")
                         .requires("tablename")
-                        .help("The key to sort the output by"), // .value_parser(["due", "group"]),
+                        .value_name("KEY[:asc|desc]")
+                        .help("The key to sort the output by: due, group or description, with an optional direction (e.g. due:desc)")
+                        .value_parser(value_parser!(SortKey)),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .visible_alias("due-after")
+                        .requires("tablename")
+                        .help("Only show tasks due from this moment, same formats as the due")
+                        .value_parser(value_parser!(Due)),
+                )
+                .arg(
+                    Arg::new("until")
+                        .long("until")
+                        .visible_alias("due-before")
+                        .requires("tablename")
+                        .help("Only show tasks due up to this moment, same formats as the due")
+                        .value_parser(value_parser!(Due)),
+                )
+                .arg(
+                    Arg::new("created-after")
+                        .long("created-after")
+                        .requires("tablename")
+                        .help("Only show tasks created after this moment, same formats as the due")
+                        .value_parser(value_parser!(Due)),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .requires("tablename")
+                        .help("The maximum number of tasks to fetch")
+                        .value_parser(value_parser!(u32).range(1..)),
+                )
+                .arg(
+                    Arg::new("page")
+                        .long("page")
+                        .requires("tablename")
+                        .conflicts_with("limit")
+                        .help("The page of tasks to show, from 1")
+                        .value_parser(value_parser!(u32).range(1..)),
+                )
+                .arg(
+                    Arg::new("page-size")
+                        .long("page-size")
+                        .requires("tablename")
+                        .conflicts_with("limit")
+                        .help(format!("How many tasks a page has, defaults to {DEFAULT_PAGE_SIZE}"))
+                        .value_parser(value_parser!(u32).range(1..)),
+                )
+                .arg(
+                    Arg::new("query")
+                        .long("query")
+                        .short('Q')
+                        .value_name("KEY=VALUE")
+                        .action(ArgAction::Append)
+                        .requires("tablename")
+                        .help("Additional query parameter forwarded to the server, can be repeated")
+                        .value_parser(value_parser!(QueryParam)),
+                )
+                .arg(
+                    Arg::new("counts")
+                        .long("counts")
+                        .short('c')
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("tablename")
+                        .help("Show how many tasks each table has"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .requires("tablename")
+                        .default_value("table")
+                        .value_parser(["table", "markdown", "csv"])
+                        .help("How to render the tasks, markdown is meant to be pasted in issues or notes and csv in spreadsheets"),
+                )
+                .arg(
+                    Arg::new("checklist")
+                        .long("checklist")
+                        .action(ArgAction::SetTrue)
+                        .requires("tablename")
+                        .help("Render the tasks as a markdown checklist ticking the completed ones, implies --format markdown"),
+                )
+                .arg(
+                    Arg::new("overdue")
+                        .long("overdue")
+                        .action(ArgAction::SetTrue)
+                        .requires("tablename")
+                        .help("Only show the tasks past their due, the most overdue first"),
+                )
+                .arg(
+                    Arg::new("absolute-dates")
+                        .long("absolute-dates")
+                        .action(ArgAction::SetTrue)
+                        .requires("tablename")
+                        .help("Show the dues in the table as dates instead of relative to now (e.g. in 2h)"),
                 ),
         )
         .subcommand(
             Command::new("create")
                 .about("Creates a new table")
+                .visible_alias("mk")
                 .arg(
                     Arg::new("tablename")
                         .required(true)
@@ -170,27 +573,57 @@ fn app_args() -> clap::ArgMatches {
                         .short('d')
                         .action(ArgAction::SetTrue)
                         .help("Set if the table has due time, defaults to false"),
+                )
+                .arg(
+                    Arg::new("no-group")
+                        .long("no-group")
+                        .action(ArgAction::SetTrue)
+                        .help("Set if the tasks of the table have no group, by default they have one"),
+                ),
+        )
+        .subcommand(
+            Command::new("drop")
+                .about("Deletes a table")
+                .arg(
+                    Arg::new("tablename")
+                        .required(true)
+                        .help("Name of the table to remove"),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .action(ArgAction::SetTrue)
+                        .help("Skip the confirmation"),
                 ),
         )
         .subcommand(
-            Command::new("drop").about("Deletes a table").arg(
-                Arg::new("tablename")
-                    .required(true)
-                    .help("Name of the table to remove"),
-            ),
+            Command::new("rename")
+                .about("Renames a table, keeping its tasks")
+                .arg(
+                    Arg::new("old")
+                        .required(true)
+                        .help("The name of the table to rename"),
+                )
+                .arg(
+                    Arg::new("new")
+                        .required(true)
+                        .help("The new name of the table"),
+                ),
         )
         .subcommand(
             Command::new("add")
                 .about("Adds a task into a table")
+                .visible_alias("new")
                 .arg(
                     Arg::new("tablename")
-                        .required(true)
-                        .help("Name of the table where to add the task"),
+                        .required(false)
+                        .help("Name of the table where to add the task, defaults to the configured default table"),
                 )
                 .group(
                     ArgGroup::new("source")
                         .required(true)
-                        .args(&["task", "file"]),
+                        .args(["task", "file", "from", "interactive"]),
                 )
                 .arg(
                     Arg::new("task")
@@ -208,6 +641,22 @@ fn app_args() -> clap::ArgMatches {
                         .help("File from where to find the description of the task to add")
                         .value_parser(value_parser!(PathBuf)),
                 )
+                .arg(
+                    Arg::new("interactive")
+                        .long("interactive")
+                        .short('i')
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["task", "file", "from", "due", "group"])
+                        .help("Ask the description, the due and the group one by one"),
+                )
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_name("FILE")
+                        .conflicts_with_all(["task", "file", "due", "group", "note", "every"])
+                        .help("Add a task for every line of a file ('-' for stdin) in the format '<name> | <due> | <group>'")
+                        .value_parser(value_parser!(PathBuf)),
+                )
                 .arg(
                     Arg::new("line")
                         .long("line")
@@ -229,7 +678,7 @@ fn app_args() -> clap::ArgMatches {
                     Arg::new("due")
                         .long("due")
                         .short('d')
-                        .help("The due of the task in one of the formats: 'hh:mm' or 'YYYY-MM-dd hh:mm'")
+                        .help("The due of the task in one of the formats: 'hh:mm[:ss]', 'YYYY-MM-dd hh:mm[:ss]', 'YYYY-MM-ddThh:mm[:ss]', '<today|tomorrow|weekday> hh:mm' or '+<N><m|h|d>'")
                         .value_parser(value_parser!(Due)),
                 )
                 .arg(
@@ -237,30 +686,69 @@ fn app_args() -> clap::ArgMatches {
                         .long("group")
                         .short('g')
                         .help("The group of the task"),
+                )
+                .arg(
+                    Arg::new("note")
+                        .long("note")
+                        .visible_alias("attach-note")
+                        .help("A longer note attached to the task"),
+                )
+                .arg(
+                    Arg::new("no-past-due")
+                        .long("no-past-due")
+                        .action(ArgAction::SetTrue)
+                        .help("Refuse a due already past instead of only warning about it"),
+                )
+                .arg(
+                    Arg::new("every")
+                        .long("every")
+                        .value_name("INTERVAL")
+                        .help("Repeat the task, e.g. 'daily', 'weekly', '2d', '1w', ignored on tables without due")
+                        .value_parser(value_parser!(Recurrence)),
                 ),
         )
         .subcommand(
             Command::new("remove")
-                .about("Removes a task from a table")
+                .about("Removes tasks from a table")
+                .visible_alias("rm")
+                .allow_missing_positional(true)
                 .arg(
                     Arg::new("tablename")
-                        .required(true)
-                        .help("Name of the table where to remove the task"),
+                        .required(false)
+                        .help("Name of the table where to remove the task, defaults to the configured default table"),
                 )
                 .arg(
                     Arg::new("desc")
-                        .required(true)
-                        .help("The description of the task to remove")
+                        .required_unless_present("name")
+                        .num_args(1..)
+                        .help("The descriptions of the tasks to remove, a task that can't be removed doesn't stop the others")
                         .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("name")
+                        .long("name")
+                        .short('n')
+                        .conflicts_with("tablename")
+                        .help("Remove the only task whose description contains this text, the positional becomes the table")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .action(ArgAction::SetTrue)
+                        .requires("name")
+                        .help("Skip the confirmation when removing by name"),
                 ),
         )
         .subcommand(
             Command::new("update")
                 .about("Updates a task from a table")
+                .allow_missing_positional(true)
                 .arg(
                     Arg::new("tablename")
-                        .required(true)
-                        .help("Name of the table where to update the task"),
+                        .required(false)
+                        .help("Name of the table where to update the task, defaults to the configured default table"),
                 )
                 .arg(
                     Arg::new("desc")
@@ -269,9 +757,10 @@ fn app_args() -> clap::ArgMatches {
                         .value_parser(value_parser!(String)),
                 )
                 .group(
-                    ArgGroup::new("source")
+                    ArgGroup::new("changes")
                         .required(true)
-                        .args(&["task", "file"]),
+                        .multiple(true)
+                        .args(["task", "file", "due", "group", "note", "every"]),
                 )
                 .arg(
                     Arg::new("task")
@@ -310,7 +799,7 @@ fn app_args() -> clap::ArgMatches {
                     Arg::new("due")
                         .long("due")
                         .short('d')
-                        .help("The due of the task in one of the formats: 'hh:mm' or 'YYYY-MM-dd hh:mm'")
+                        .help("The due of the task in one of the formats: 'hh:mm[:ss]', 'YYYY-MM-dd hh:mm[:ss]', 'YYYY-MM-ddThh:mm[:ss]', '<today|tomorrow|weekday> hh:mm' or '+<N><m|h|d>'")
                         .value_parser(value_parser!(Due)),
                 )
                 .arg(
@@ -318,88 +807,494 @@ fn app_args() -> clap::ArgMatches {
                         .long("group")
                         .short('g')
                         .help("The group of the task"),
+                )
+                .arg(
+                    Arg::new("note")
+                        .long("note")
+                        .visible_alias("attach-note")
+                        .help("A longer note attached to the task"),
+                )
+                .arg(
+                    Arg::new("no-past-due")
+                        .long("no-past-due")
+                        .action(ArgAction::SetTrue)
+                        .help("Refuse a due already past instead of only warning about it"),
+                )
+                .arg(
+                    Arg::new("every")
+                        .long("every")
+                        .value_name("INTERVAL")
+                        .help("Repeat the task, e.g. 'daily', 'weekly', '2d', '1w', ignored on tables without due")
+                        .value_parser(value_parser!(Recurrence)),
+                ),
+        )
+        .subcommand(
+            Command::new("duplicate")
+                .about("Duplicates a task of a table")
+                .allow_missing_positional(true)
+                .arg(
+                    Arg::new("tablename")
+                        .required(false)
+                        .help("Name of the table where to duplicate the task, defaults to the configured default table"),
+                )
+                .arg(
+                    Arg::new("desc")
+                        .required(true)
+                        .help("The description of the task to duplicate")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("task")
+                        .long("task")
+                        .short('t')
+                        .alias("name")
+                        .help("The description of the copy, defaults to the original one")
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("move")
+                .about("Moves a task to another group or table")
+                .visible_alias("mv")
+                .allow_missing_positional(true)
+                .arg(
+                    Arg::new("tablename")
+                        .required(false)
+                        .help("Name of the table of the task, defaults to the configured default table"),
+                )
+                .arg(
+                    Arg::new("desc")
+                        .required(true)
+                        .help("The description of the task to move")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("to-group")
+                        .long("to-group")
+                        .short('g')
+                        .help("The group to move the task to")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("to-table")
+                        .long("to-table")
+                        .short('t')
+                        .help("The table to move the task to")
+                        .value_parser(value_parser!(String)),
+                )
+                .group(
+                    ArgGroup::new("destination")
+                        .args(["to-group", "to-table"])
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("done")
+                .about("Marks tasks as completed")
+                .visible_alias("complete")
+                .allow_missing_positional(true)
+                .arg(
+                    Arg::new("tablename")
+                        .required(false)
+                        .help("Name of the table of the tasks, defaults to the configured default table"),
+                )
+                .arg(
+                    Arg::new("desc")
+                        .required(true)
+                        .num_args(1..)
+                        .help("The descriptions of the tasks to complete")
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Manages the local configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("set")
+                        .about("Sets a configuration value")
+                        .arg(
+                            Arg::new("key")
+                                .required(true)
+                                .help("The configuration key to set")
+                                .value_parser([
+                                    "default_table",
+                                    "normalize_whitespace",
+                                    "timezone",
+                                    "backend",
+                                    "insecure",
+                                    "retries",
+                                    "timeout_secs",
+                                    "frontend",
+                                ]),
+                        )
+                        .arg(
+                            Arg::new("value")
+                                .required(true)
+                                .help("The value to set the key to"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("check")
+                        .about("Checks the config file and reports what is wrong with it"),
                 ),
         )
+        .subcommand(
+            Command::new("profile")
+                .about("Manages the accounts")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Adds an account")
+                        .arg(
+                            Arg::new("name")
+                                .required(true)
+                                .help("The name of the profile"),
+                        )
+                        .arg(
+                            Arg::new("backend")
+                                .long("backend")
+                                .help("The url of the server of the account, defaults to the built-in one"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("use")
+                        .about("Switches to an account")
+                        .arg(
+                            Arg::new("name")
+                                .required(true)
+                                .help("The name of the profile"),
+                        ),
+                )
+                .subcommand(Command::new("list").about("Lists the accounts")),
+        )
         .subcommand(
             Command::new("clear")
                 .about("Clears completely a table")
                 .arg(
                     Arg::new("tablename")
+                        .required(false)
+                        .help("Name of the table where to clear, defaults to the configured default table"),
+                )
+                .arg(
+                    Arg::new("completed")
+                        .long("completed")
+                        .action(ArgAction::SetTrue)
+                        .help("Only remove the completed tasks"),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .action(ArgAction::SetTrue)
+                        .help("Skip the confirmation"),
+                ),
+        )
+        .subcommand(
+            Command::new("show")
+                .about("Shows every field of a task")
+                .allow_missing_positional(true)
+                .arg(
+                    Arg::new("tablename")
+                        .required(false)
+                        .help("Name of the table of the task, defaults to the configured default table"),
+                )
+                .arg(
+                    Arg::new("desc")
+                        .required(true)
+                        .help("The description of the task, or a part of it matching only one task")
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("search")
+                .about("Searches the tasks whose description contains a text, in a table or in all of them")
+                .visible_alias("find")
+                .arg(
+                    Arg::new("query")
+                        .required(true)
+                        .help("The text to search, case insensitive")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("table")
+                        .long("table")
+                        .short('t')
+                        .help("Only search this table")
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Counts the tasks of a table: in total, overdue, due today and by group")
+                .arg(
+                    Arg::new("tablename")
+                        .required(false)
+                        .help("Name of the table to count, defaults to the configured default table"),
+                ),
+        )
+        .subcommand(
+            Command::new("notify")
+                .about("Sends a desktop notification for every overdue task, meant for cron or systemd timers")
+                .arg(
+                    Arg::new("window")
+                        .long("window")
+                        .short('w')
+                        .value_name("MINUTES")
+                        .default_value("60")
+                        .help("Don't notify again a task notified in the last MINUTES")
+                        .value_parser(value_parser!(u32)),
+                ),
+        )
+        .subcommand(
+            Command::new("undo")
+                .about("Reverts the last add, update, remove, clear or drop"),
+        )
+        .subcommand(
+            Command::new("tui").about("Opens the terminal interface to browse and edit the tasks"),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Prints the completion script of a shell")
+                .arg(
+                    Arg::new("shell")
                         .required(true)
-                        .help("Name of the table where to clear"),
+                        .help("The shell to complete for")
+                        .value_parser(value_parser!(Shell)),
                 ),
         )
-        .get_matches()
 }
 
 const ENV_FILE: &str = include_str!("env_path.txt");
 
-/// Handles all the matching of the cli areguments
-fn main() -> Result<()> {
-    dotenv::from_path(ENV_FILE.trim()).unwrap();
+fn main() -> ExitCode {
+    // the errors before the args are parsed are reported in the human readable mode
+    let mut output = OutputMode::Human;
+    match try_main(&mut output) {
+        // a dry run stops at a request whose answer is needed, it already got printed
+        Ok(()) | Err(Error::DryRun) => ExitCode::SUCCESS,
+        Err(err) => output.report_error(err),
+    }
+}
 
-    let log_path = find_log_path();
-    let file_appender = FileAppender::builder()
-        .encoder(Box::new(PatternEncoder::new(
-            "{d(%Y-%m-%d %H:%M:%S)(utc)} - {h({l})}: {m}{n}",
-        )))
-        .build(log_path)
-        .unwrap();
+/// Runs the app, the errors are reported by `main` with the exit code of their category
+///
+/// # Args
+/// - output: set to the output mode asked by the args, the errors are reported in it
+fn try_main(output: &mut OutputMode) -> Result<()> {
+    // the env file is optional, without it the platform directories are used
+    if !ENV_FILE.trim().is_empty() {
+        dotenv::from_path(ENV_FILE.trim()).unwrap();
+    }
+    let logger = init_logging();
 
-    let config = log4rs::Config::builder()
-        .appender(Appender::builder().build("file_logger", Box::new(file_appender)))
-        .logger(
-            Logger::builder()
-                .appender("file_logger")
-                .build("app::backend", log::LevelFilter::Info),
-        )
-        .build(
-            Root::builder()
-                .appender("file_logger")
-                .build(log::LevelFilter::Info),
-        )
-        .unwrap();
+    // a lenient first pass over the args, the dues in them can only be parsed once the
+    // config gave the timezone
+    let early_args = app_command().ignore_errors(true).try_get_matches().ok();
+    // `config check` looks at the file as it is, loading it would refuse or reset a broken one
+    let checking_config = early_args
+        .as_ref()
+        .and_then(|args| args.subcommand_matches("config"))
+        .is_some_and(|config| config.subcommand_name() == Some("check"));
 
-    log4rs::init_config(config).unwrap();
+    //init config, the timezone has to be known before parsing the dues in the args
+    let config = match checking_config {
+        true => Config::default(),
+        false => Config::get_config()?,
+    };
+    set_zone(due_zone(&config, early_args.as_ref())?);
+
+    let args = app_command().get_matches();
+    // without a subcommand the cli frontend only shows the help
+    if args.subcommand().is_none() && config.frontend == Frontend::Cli {
+        eprint!("{}", app_command().render_help());
+        std::process::exit(2);
+    }
+
+    // the completions don't need an account or the server
+    if let Some(("completions", sub_matches)) = args.subcommand() {
+        let shell = *sub_matches.get_one::<Shell>("shell").unwrap();
+        generate(shell, &mut app_command(), "rsm", &mut io::stdout());
+        return Ok(());
+    }
+
+    *output = OutputMode::from_args(&args);
+    set_verbosity(&logger, args.get_count("verbose"));
+    // https://no-color.org: any non empty NO_COLOR turns the colors off
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    set_color(!args.get_flag("no-color") && !no_color && io::stdout().is_terminal());
+    if let Some(path) = args.get_one::<PathBuf>("output") {
+        set_output_file(path.clone());
+    }
+    set_print_request_id(args.get_flag("print-request-id"));
+    set_quiet(args.get_flag("quiet"));
+    // the spinner would end up mixed with the json, and when nothing is printed on stdout
+    // there is no result to wait for
+    set_spinner(
+        *output == OutputMode::Human && !args.get_flag("quiet") && !args.contains_id("output"),
+    );
+
+    run(args, *output, config).and_then(|_| flush_output())
+}
+
+/// Returns the zone the dues are typed and shown in: the one of `--due-timezone`, the
+/// `timezone` of the config or the local one. The dues are parsed with the args so the
+/// flag is read from a lenient first pass, an invalid one is then reported by the real one
+///
+/// # Args
+/// - config: the config, for its `timezone`
+/// - early_args: the args of the lenient first pass, `None` if it failed (e.g. `--help`)
+fn due_zone(config: &Config, early_args: Option<&clap::ArgMatches>) -> Result<Zone> {
+    let flag = early_args.and_then(|args| args.get_one::<Zone>("due-timezone").copied());
+    match (flag, &config.timezone) {
+        (Some(zone), _) => Ok(zone),
+        (None, Some(timezone)) => timezone.parse(),
+        (None, None) => Ok(Zone::Local),
+    }
+}
 
-    //init config and if it is the first time running show the default prompt
-    let mut config = Config::get_config()?;
-    let args = app_args();
+/// Sets up the file logger, the returned handle lets `set_verbosity` add the console one
+fn init_logging() -> log4rs::Handle {
+    log4rs::init_config(logging_config(None)).unwrap()
+}
+
+/// Builds the logging config, the file always gets the `info` level and above while the
+/// console (stderr, so stdout stays clean) gets the `console` level, if any
+///
+/// # Args
+/// - console: the level logged on stderr, nothing is logged there when `None`
+fn logging_config(console: Option<log::LevelFilter>) -> log4rs::Config {
+    let log_path = find_log_path();
+    let file_appender = FileAppender::builder()
+        .encoder(Box::new(PatternEncoder::new(
+            "{d(%Y-%m-%d %H:%M:%S)(utc)} - {h({l})}: {m}{n}",
+        )))
+        .build(log_path)
+        .unwrap();
+
+    let mut config = log4rs::Config::builder().appender(
+        Appender::builder()
+            .filter(Box::new(ThresholdFilter::new(log::LevelFilter::Info)))
+            .build("file_logger", Box::new(file_appender)),
+    );
+    let mut root = Root::builder().appender("file_logger");
+
+    if let Some(level) = console {
+        let console_appender = ConsoleAppender::builder()
+            .target(Target::Stderr)
+            .encoder(Box::new(PatternEncoder::new("{h({l})}: {m}{n}")))
+            .build();
+        config = config.appender(
+            Appender::builder()
+                .filter(Box::new(ThresholdFilter::new(level)))
+                .build("console_logger", Box::new(console_appender)),
+        );
+        root = root.appender("console_logger");
+    }
+
+    let level = console.map_or(log::LevelFilter::Info, |level| {
+        level.max(log::LevelFilter::Info)
+    });
+    config
+        .logger(
+            Logger::builder()
+                .appender("file_logger")
+                .build("app::backend", log::LevelFilter::Info),
+        )
+        .build(root.build(level))
+        .unwrap()
+}
+
+/// Logs on stderr too when asked by `-v` (`info`) or `-vv` (`debug`, every request and the
+/// status of its response), `RUST_LOG` set to a level (e.g. `debug`) wins over the flags
+///
+/// # Args
+/// - handle: the handle of the logger made by `init_logging`
+/// - verbose: how many times `-v` was given
+fn set_verbosity(handle: &log4rs::Handle, verbose: u8) {
+    let from_env = env::var("RUST_LOG")
+        .ok()
+        .and_then(|level| level.trim().parse::<log::LevelFilter>().ok());
+    let level = from_env.or(match verbose {
+        0 => None,
+        1 => Some(log::LevelFilter::Info),
+        _ => Some(log::LevelFilter::Debug),
+    });
+
+    if level.is_some() {
+        handle.set_config(logging_config(level));
+    }
+}
+
+/// Handles all the matching of the cli areguments
+fn run(args: clap::ArgMatches, output: OutputMode, mut config: Config) -> Result<()> {
+    let backend = args.get_one::<String>("backend");
+    let insecure = args.get_flag("insecure") || config.insecure;
+    let dry_run = args.get_flag("dry-run");
+    if insecure {
+        eprintln!(
+            "\x1b[31mWARNING: TLS certificate verification is disabled, the connection is not safe\x1b[0m"
+        );
+    }
 
-    let mut api = if !args.subcommand_matches("new-key").is_some() {
-        if config.first_run {
-            let api = Api::new_without_token();
-            show_first_run_prompt(&api, &mut config)?;
-            config.first_run = false;
-            config.update_config()?;
+    // if it is the first time running show the default prompt
+    let mut api = match args.subcommand_name() {
+        Some("new-key") | Some("config") | Some("whoami") | Some("profile") => {
+            Api::new_without_token(&config)
+        }
+        _ => {
+            let expired = config
+                .token
+                .clone()
+                .is_some_and(|token| Token::from(token).is_expired(Utc::now()));
+            if config.first_run || expired {
+                let mut api = Api::new_without_token(&config);
+                api.set_insecure(insecure);
+                api.set_dry_run(dry_run);
+                if let Some(backend) = backend {
+                    api.set_backend(backend.clone());
+                }
+                if config.first_run {
+                    show_first_run_prompt(&api, &mut config, output)?;
+                    config.first_run = false;
+                } else {
+                    refresh_token(&api, &mut config)?;
+                }
+                config.update_config()?;
+            }
+            Api::new(&config)?
         }
-        Api::new()?
-    } else {
-        Api::new_without_token()
     };
 
+    api.set_insecure(insecure);
+    api.set_dry_run(dry_run);
+    if let Some(backend) = backend {
+        api.set_backend(backend.clone());
+    }
+
     match args.subcommand() {
-        Some(("new-key", _)) => {
-            println!("Please input your credentials: ");
-            print!("username: ");
-            io::stdout().flush().map_err(|_| Error::RsmFailed)?;
+        Some(("new-key", sub_matches)) => {
+            eprintln!("Please input your credentials: ");
+            let username = match sub_matches.get_one::<String>("username") {
+                Some(username) => username.to_owned(),
+                None => {
+                    eprint!("username: ");
+                    io::stderr().flush().map_err(|_| Error::RsmFailed)?;
 
-            let mut username = String::new();
-            io::stdin()
-                .read_line(&mut username)
-                .map_err(|_| Error::RsmFailed)?;
+                    let mut username = String::new();
+                    io::stdin()
+                        .read_line(&mut username)
+                        .map_err(|_| Error::RsmFailed)?;
+                    username.trim().to_owned()
+                }
+            };
 
-            let password =
-                rpassword::prompt_password("password: ").map_err(|_| Error::RsmFailed)?;
+            let password = read_password()?;
 
             // prettier output
-            println!("");
-            let handle = terminal_spinners::SpinnerBuilder::new()
-                .spinner(&terminal_spinners::DOTS)
-                .text("Making a new key...")
-                .start();
-            let res = api.post_lostkey(&username, &password)?;
-            handle.done();
+            eprintln!();
+            let res = with_spinner(|| api.post_lostkey(&username, &password))?;
             log::info!("Successfully sent POST lostkey request and received response");
 
             let res_type = &res.as_any();
@@ -407,18 +1302,24 @@ fn main() -> Result<()> {
                 res.print();
                 return Err(Error::FailedToUpdateKey);
             } else if res_type.is::<SuccessfulResponse>() {
-                res.print();
-                println!("\x1b[34mNow login again\x1b[0m\n");
+                print_response(res.as_ref(), output)?;
+                if output == OutputMode::Human {
+                    println!(
+                        "\x1b[33mStore the new key somewhere safe, it is needed to log in again\x1b[0m"
+                    );
+                    println!("\x1b[34mNow login again\x1b[0m\n");
+                }
                 config.first_run = true;
                 config.update_config()?;
 
-                let (key, token) = login(&api).map_err(|e| {
+                let (key, token) = login(&api, output).map_err(|e| {
                     log::error!("{e:?}");
                     e
                 })?;
-                config.key = Some(key.0.replace("\n", ""));
+                config.key = Some(key.0.replace('\n', ""));
                 let token: String = token.into();
-                config.token = Some(token.replace("\n", ""));
+                config.token = Some(token.replace('\n', ""));
+                config.username = Some(username);
                 config.first_run = false;
                 config.update_config()?;
 
@@ -435,44 +1336,130 @@ fn main() -> Result<()> {
                 Choice::No => false,
             };
 
-            match api.post_logout(logout) {
+            match with_spinner(|| api.post_logout(logout)) {
                 Ok(res) => {
                     log::info!("Successfully sent POST logout request and received response");
-                    if logout {
-                        // reset config
+                    // the token is only dropped once the server ended the session, so a
+                    // failed logout can be retried. The key stays to log in again
+                    if logout && res.as_any().is::<SuccessfulResponse>() {
                         config.token = None;
                         config.first_run = true;
-                        config.key = None;
+                        config.update_config()?;
                     }
-                    res.print();
+                    print_response(res.as_ref(), output)?;
                 }
                 Err(err) => {
                     log::error!("Error occurred while logging out: {:?}", err);
                     return Err(err);
                 }
             }
-            config.update_config()?;
-            api.update_token()?;
         }
         Some(("list", sub_matches)) => {
             let tablename = sub_matches
                 .get_one::<String>("tablename")
                 .map(|s| s.as_str());
             let group = sub_matches.get_one::<String>("group").map(|s| s.as_str());
-            let sort_key = sub_matches.get_one::<String>("sort-by").map(|s| s.as_str());
+            let sort_key = sub_matches.get_one::<SortKey>("sort-by").copied();
+
+            let created_after = sub_matches
+                .get_one::<Due>("created-after")
+                .map(|due| due.to_string());
+            let limit = sub_matches
+                .get_one::<u32>("limit")
+                .map(|limit| limit.to_string());
+
+            // a page size alone shows the first page
+            let page = match (
+                sub_matches.get_one::<u32>("page"),
+                sub_matches.get_one::<u32>("page-size"),
+            ) {
+                (None, None) => None,
+                (page, size) => {
+                    let size = size.copied().unwrap_or(DEFAULT_PAGE_SIZE) as usize;
+                    let offset = (page.copied().unwrap_or(1) as usize - 1) * size;
+                    Some((offset, size))
+                }
+            };
+            let page_params = page.map(|(offset, size)| (offset.to_string(), size.to_string()));
 
             let mut opts_map: HashMap<&str, &str> = HashMap::new();
+            // the generic params go first so that the dedicated flags win on conflicts
+            for param in sub_matches
+                .get_many::<QueryParam>("query")
+                .unwrap_or_default()
+            {
+                opts_map.insert(&param.key, &param.value);
+            }
             if let Some(group_value) = group {
                 opts_map.insert("group", group_value);
             }
-            if let Some(sort_by_value) = sort_key {
-                opts_map.insert("sort_by", sort_by_value);
+            // the server may not sort, the tasks are sorted again once fetched
+            if let Some(sort_key) = sort_key {
+                opts_map.insert("sort_by", sort_key.field.name());
+            }
+            if let Some(created_after) = &created_after {
+                opts_map.insert("created_after", created_after);
+            }
+            if let Some(limit) = &limit {
+                opts_map.insert("limit", limit);
+            }
+            if let Some((offset, size)) = &page_params {
+                opts_map.insert("offset", offset);
+                opts_map.insert("limit", size);
             }
 
-            match api.get_tasks(tablename, opts_map) {
-                Ok(res) => {
+            let since = sub_matches.get_one::<Due>("since").map(|due| due.0);
+            let until = sub_matches.get_one::<Due>("until").map(|due| due.0);
+            check_due_window(since, until)?;
+            let show_counts = sub_matches.get_flag("counts");
+            let format = sub_matches.get_one::<String>("format").unwrap().as_str();
+            let checklist = sub_matches.get_flag("checklist");
+            let absolute_dues = sub_matches.get_flag("absolute-dates");
+            let overdue = sub_matches.get_flag("overdue");
+
+            match with_spinner(|| api.get_tasks(tablename, opts_map)) {
+                Ok(listing) => {
                     log::info!("Successfully sent GET list request and received response");
-                    res.print();
+                    match listing {
+                        TaskListing::Tables(tables) if show_counts => {
+                            print_response(&with_task_counts(&api, &tables)?, output)?;
+                        }
+                        TaskListing::Tasks(mut tasks) => {
+                            tasks.absolute_dues = absolute_dues;
+                            // the server may not page, the page is cut again
+                            if let Some((offset, size)) = page {
+                                tasks.paginate(offset, size);
+                            }
+                            // the server may ignore the group, it is filtered again
+                            if let Some(group) = group {
+                                tasks.retain_group(group);
+                                if tasks.res.is_empty()
+                                    && output == OutputMode::Human
+                                    && format == "table"
+                                {
+                                    emit(&format!("No tasks in group {group}\n"));
+                                    return Ok(());
+                                }
+                            }
+                            tasks.retain_due_between(since, until);
+                            if overdue {
+                                tasks.retain_overdue(server_now());
+                            }
+                            if let Some(sort_key) = sort_key {
+                                tasks.sort_by(sort_key);
+                            }
+                            if output == OutputMode::Json {
+                                print_response(&tasks, output)?;
+                            } else if format == "csv" {
+                                emit(&tasks.to_csv());
+                            } else if format == "markdown" || checklist {
+                                emit(&tasks.to_markdown(checklist));
+                            } else {
+                                tasks.print();
+                            }
+                        }
+                        listing => print_response(&listing, output)?,
+                    }
                 }
                 Err(err) => {
                     log::error!("Error occurred while fetching tasks: {:?}", err);
@@ -481,20 +1468,16 @@ fn main() -> Result<()> {
             }
         }
         Some(("create", sub_matches)) => {
-            let tablename = sub_matches
-                .get_one::<String>("tablename")
-                .map(|s| s.to_owned())
-                .unwrap();
+            let tablename =
+                validate_tablename(sub_matches.get_one::<String>("tablename").unwrap())?;
 
-            let has_due = sub_matches
-                .get_one::<bool>("due")
-                .map(|b| b.clone())
-                .unwrap();
+            let has_due = sub_matches.get_flag("due");
+            let has_group = !sub_matches.get_flag("no-group");
 
-            match api.create_table(tablename, has_due) {
+            match with_spinner(|| api.create_table(tablename, has_due, has_group)) {
                 Ok(res) => {
                     log::info!("Successfully sent POST create table request and received response");
-                    res.print();
+                    print_response(res.as_ref(), output)?;
                 }
                 Err(err) => {
                     log::error!("Error occurred while fetching tasks: {:?}", err);
@@ -508,12 +1491,23 @@ fn main() -> Result<()> {
                 .map(|s| s.to_owned())
                 .unwrap();
 
-            match api.remove_table(tablename) {
+            if !sub_matches.get_flag("yes")
+                && !confirm(&format!("Are you sure you want to drop '{tablename}'?"))?
+            {
+                return Ok(());
+            }
+
+            let snapshot = with_spinner(|| api.snapshot_table(&tablename));
+
+            match with_spinner(|| api.remove_table(tablename)) {
                 Ok(res) => {
                     log::info!(
                         "Successfully sent DELETE remove table request and received response"
                     );
-                    res.print();
+                    if let Some(op) = snapshot {
+                        record_op(&config, res.as_ref(), op);
+                    }
+                    print_response(res.as_ref(), output)?;
                 }
                 Err(err) => {
                     log::error!("Error occurred while fetching tasks: {:?}", err);
@@ -521,48 +1515,116 @@ fn main() -> Result<()> {
                 }
             }
         }
+        Some(("rename", sub_matches)) => {
+            let old = sub_matches.get_one::<String>("old").unwrap();
+            let new = sub_matches.get_one::<String>("new").unwrap();
+
+            match with_spinner(|| api.rename_table(old, new)) {
+                Ok(res) => {
+                    log::info!("Successfully renamed table '{old}' and received response");
+                    print_response(res.as_ref(), output)?;
+                }
+                Err(err) => {
+                    log::error!("Error occurred while renaming table: {:?}", err);
+                    return Err(err);
+                }
+            }
+        }
         Some(("add", sub_matches)) => {
-            // if tablename isnt present something really wrong happened
-            let tablename = sub_matches
-                .get_one::<String>("tablename")
-                .map(|s| s.clone())
-                .unwrap();
+            let tablename = resolve_tablename(sub_matches, &config)?;
+            if let Some(from) = sub_matches.get_one::<PathBuf>("from") {
+                let no_past_due = sub_matches.get_flag("no-past-due");
+                let contents = if from == Path::new("-") {
+                    io::read_to_string(io::stdin())
+                } else {
+                    fs::read_to_string(from)
+                }
+                .map_err(|e| Error::FailedToResolveFile {
+                    detail: e.to_string(),
+                })?;
+                let report =
+                    with_spinner(|| api.add_tasks(&config, &tablename, &contents, no_past_due));
+                log::info!("Added {} tasks to {tablename}", report.succeeded());
+                report.print(output);
+                return Ok(());
+            }
+
             let task = sub_matches.get_one::<String>("task");
             let file = sub_matches.get_one::<PathBuf>("file");
             let line = sub_matches.get_one::<u16>("line");
             let range = sub_matches.get_one::<LineRange>("range");
-            let due = sub_matches.get_one::<Due>("due");
-            let group = sub_matches.get_one::<String>("group");
+            let mut resolved_due = sub_matches.get_one::<Due>("due").cloned();
+            let mut group = sub_matches.get_one::<String>("group").cloned();
+            let note = sub_matches.get_one::<String>("note");
+            let every = sub_matches
+                .get_one::<Recurrence>("every")
+                .map(|rule| rule.to_string());
 
             // get the task
-            let task = if let Some(file) = file {
+            let task = if sub_matches.get_flag("interactive") {
+                // the wizard needs somebody answering
+                if !io::stdin().is_terminal() {
+                    return Err(Error::NoTerminal);
+                }
+                let answers = add_wizard(&mut io::stdin().lock(), &mut io::stderr())?;
+                resolved_due = answers.due;
+                group = answers.group;
+                answers.description
+            } else if let Some(file) = file {
                 // file input
-                let task = resolve_file_input(file, line, range).map_err(|e| {
-                    Error::FailedToResolveFile {
-                        detail: e.to_string(),
-                    }
-                })?;
-                task
+                resolve_file_input(file, line, range).map_err(|e| Error::FailedToResolveFile {
+                    detail: e.to_string(),
+                })?
             } else {
                 // text input
                 task.map_or("".to_owned(), |task| task.clone())
             };
 
+            if sub_matches.get_flag("no-past-due")
+                && resolved_due.as_ref().is_some_and(Due::is_past)
+            {
+                return Err(Error::PastDue);
+            }
+            // the server would reject the whole task, better to tell why before sending it
+            if resolved_due.is_some() && !with_spinner(|| api.table_has_due(&tablename))? {
+                return Err(Error::TableDoesNotSupportDue);
+            }
+            let due = resolved_due.as_ref().map(|due| due.to_string());
             let mut opts_map: HashMap<&str, &str> = HashMap::new();
-            if let Some(due) = due {
-                opts_map.insert("due", &due.0);
+            if let Some(due) = &due {
+                opts_map.insert("due", due);
             }
 
-            if let Some(group) = group {
+            if let Some(group) = &group {
                 opts_map.insert("group", group);
             }
 
+            if let Some(note) = note {
+                opts_map.insert("note", note);
+            }
+
+            // the recurrence needs a due, tables without one would reject the whole task
+            if let Some(every) = &every {
+                if with_spinner(|| api.table_has_due(&tablename))? {
+                    opts_map.insert("recurrence", every);
+                } else {
+                    eprintln!("\x1b[33m'{tablename}' has no due, the recurrence is ignored\x1b[0m");
+                }
+            }
+
             opts_map.insert("description", &task);
 
-            match api.add_task(tablename, opts_map) {
+            let op = Op::Add {
+                table: tablename.clone(),
+                description: normalize_description(&task, config.normalize_whitespace)?,
+            };
+
+            match with_spinner(|| api.add_task(tablename, opts_map)) {
                 Ok(res) => {
                     log::info!("Successfully sent POST add request and received response");
-                    res.print();
+                    record_op(&config, res.as_ref(), op);
+                    print_response(res.as_ref(), output)?;
+                    print_resolved_due(res.as_ref(), resolved_due.as_ref(), output);
                 }
                 Err(err) => {
                     log::error!("Error occurred while adding task: {:?}", err);
@@ -571,19 +1633,66 @@ fn main() -> Result<()> {
             }
         }
         Some(("remove", sub_matches)) => {
-            let tablename = sub_matches
-                .get_one::<String>("tablename")
-                .map(|s| s.to_owned())
-                .unwrap();
-            let desc = sub_matches
-                .get_one::<String>("desc")
-                .map(|s| s.to_owned())
-                .unwrap();
+            let descs: Vec<String> = sub_matches
+                .get_many::<String>("desc")
+                .unwrap_or_default()
+                .cloned()
+                .collect();
+            // several tasks are removed as a batch, a failure doesn't stop the others
+            if descs.len() > 1 {
+                let tablename = resolve_tablename(sub_matches, &config)?;
+                // the snapshots are only needed by undo, the removal goes on without them
+                let tasks = with_spinner(|| api.fetch_tasks(&tablename))
+                    .map(|tasks| tasks.res)
+                    .unwrap_or_default();
+                let report = with_spinner(|| api.remove_tasks(&tablename, &descs));
+                record_removed(&config, &tablename, tasks, &report);
+
+                log::info!("Removed {} tasks from {tablename}", report.succeeded());
+                report.print(output);
+                return Ok(());
+            }
 
-            match api.remove_task(tablename, desc) {
+            let (tablename, task) = match sub_matches.get_one::<String>("name") {
+                Some(name) => {
+                    // with --name the only positional left is the table
+                    let tablename = match sub_matches.get_one::<String>("desc") {
+                        Some(tablename) => tablename.to_owned(),
+                        None => resolve_tablename(sub_matches, &config)?,
+                    };
+                    let task = with_spinner(|| api.find_task_by_name(&tablename, name))?;
+
+                    if !sub_matches.get_flag("yes")
+                        && !confirm(&format!("Remove '{}'?", task.description))?
+                    {
+                        return Ok(());
+                    }
+                    (tablename, Some(task))
+                }
+                None => {
+                    let tablename = resolve_tablename(sub_matches, &config)?;
+                    let desc = sub_matches.get_one::<String>("desc").unwrap();
+                    // the snapshot is only needed by undo, the removal goes on without it
+                    let task = with_spinner(|| api.find_task(&tablename, desc)).ok();
+                    (tablename, task)
+                }
+            };
+            let desc = match &task {
+                Some(task) => task.description.clone(),
+                None => sub_matches.get_one::<String>("desc").cloned().unwrap(),
+            };
+            let op = task.map(|task| Op::Remove {
+                table: tablename.clone(),
+                tasks: vec![task],
+            });
+
+            match with_spinner(|| api.remove_task(tablename, desc)) {
                 Ok(res) => {
                     log::info!("Successfully sent DELETE task request and received response");
-                    res.print();
+                    if let Some(op) = op {
+                        record_op(&config, res.as_ref(), op);
+                    }
+                    print_response(res.as_ref(), output)?;
                 }
                 Err(err) => {
                     log::error!("Error occurred while removing task: {:?}", err);
@@ -592,50 +1701,84 @@ fn main() -> Result<()> {
             }
         }
         Some(("update", sub_matches)) => {
-            // if tablename or the old desc isnt present something really wrong happened
-            let tablename = sub_matches
-                .get_one::<String>("tablename")
-                .map(|s| s.clone())
-                .unwrap();
-            let old_desc = sub_matches
-                .get_one::<String>("desc")
-                .map(|s| s.clone())
-                .unwrap();
+            // if the old desc isnt present something really wrong happened
+            let tablename = resolve_tablename(sub_matches, &config)?;
+            let old_desc = sub_matches.get_one::<String>("desc").cloned().unwrap();
             let task = sub_matches.get_one::<String>("task");
             let file = sub_matches.get_one::<PathBuf>("file");
             let line = sub_matches.get_one::<u16>("line");
             let range = sub_matches.get_one::<LineRange>("range");
-            let due = sub_matches.get_one::<Due>("due");
+            let resolved_due = sub_matches.get_one::<Due>("due");
+            if sub_matches.get_flag("no-past-due") && resolved_due.is_some_and(Due::is_past) {
+                return Err(Error::PastDue);
+            }
+            let due = resolved_due.map(|d| d.to_string());
             let group = sub_matches.get_one::<String>("group");
+            let note = sub_matches.get_one::<String>("note");
+            let every = sub_matches
+                .get_one::<Recurrence>("every")
+                .map(|rule| rule.to_string());
 
+            // without a new description the old one is kept
             let task = if let Some(file) = file {
                 // file input
-                let task = resolve_file_input(file, line, range).map_err(|e| {
+                Some(resolve_file_input(file, line, range).map_err(|e| {
                     Error::FailedToResolveFile {
                         detail: e.to_string(),
                     }
-                })?;
-                task
+                })?)
             } else {
                 // text input
-                task.map_or("".to_owned(), |task| task.clone())
+                task.cloned()
             };
 
             let mut opts_map: HashMap<&str, &str> = HashMap::new();
-            if let Some(due) = due {
-                opts_map.insert("due", &due.0);
+            if let Some(due) = &due {
+                opts_map.insert("due", due);
             }
 
             if let Some(group) = group {
                 opts_map.insert("group", group);
             }
 
-            opts_map.insert("description", &task);
+            if let Some(note) = note {
+                opts_map.insert("note", note);
+            }
+
+            // the recurrence needs a due, tables without one would reject the whole task
+            if let Some(every) = &every {
+                if with_spinner(|| api.table_has_due(&tablename))? {
+                    opts_map.insert("recurrence", every);
+                } else {
+                    eprintln!("\x1b[33m'{tablename}' has no due, the recurrence is ignored\x1b[0m");
+                }
+            }
+
+            if let Some(task) = &task {
+                opts_map.insert("description", task);
+            }
+
+            // the previous values are only needed by undo, the update goes on without them
+            let op = match with_spinner(|| api.find_task(&tablename, &old_desc)) {
+                Ok(previous) => Some(Op::Update {
+                    table: tablename.clone(),
+                    description: match &task {
+                        Some(task) => normalize_description(task, config.normalize_whitespace)?,
+                        None => old_desc.clone(),
+                    },
+                    previous,
+                }),
+                Err(_) => None,
+            };
 
-            match api.update_task(tablename, old_desc, opts_map) {
+            match with_spinner(|| api.update_task(tablename, old_desc, opts_map)) {
                 Ok(res) => {
                     log::info!("Successfully sent PUT update request and received response");
-                    res.print();
+                    if let Some(op) = op {
+                        record_op(&config, res.as_ref(), op);
+                    }
+                    print_response(res.as_ref(), output)?;
+                    print_resolved_due(res.as_ref(), resolved_due, output);
                 }
                 Err(err) => {
                     log::error!("Error occurred while adding task: {:?}", err);
@@ -643,16 +1786,313 @@ fn main() -> Result<()> {
                 }
             }
         }
+        Some(("duplicate", sub_matches)) => {
+            let tablename = resolve_tablename(sub_matches, &config)?;
+            let desc = sub_matches.get_one::<String>("desc").unwrap();
+            let task = sub_matches.get_one::<String>("task");
+
+            let original = with_spinner(|| api.find_task(&tablename, desc))?;
+            let due = original.due.map(|due| Due(due).to_string());
+
+            let mut opts_map: HashMap<&str, &str> = HashMap::new();
+            if let Some(due) = &due {
+                opts_map.insert("due", due);
+            }
+            opts_map.insert("group", &original.group);
+            if let Some(note) = &original.note {
+                opts_map.insert("note", note);
+            }
+            if let Some(recurrence) = &original.recurrence {
+                opts_map.insert("recurrence", recurrence);
+            }
+            let description = task.map_or(&original.description, |task| task);
+            opts_map.insert("description", description);
+
+            let op = Op::Add {
+                table: tablename.clone(),
+                description: normalize_description(description, config.normalize_whitespace)?,
+            };
+
+            match with_spinner(|| api.add_task(tablename, opts_map)) {
+                Ok(res) => {
+                    log::info!("Successfully sent POST duplicate request and received response");
+                    record_op(&config, res.as_ref(), op);
+                    print_response(res.as_ref(), output)?;
+                }
+                Err(err) => {
+                    log::error!("Error occurred while duplicating task: {:?}", err);
+                    return Err(err);
+                }
+            }
+        }
+        Some(("move", sub_matches)) => {
+            let tablename = resolve_tablename(sub_matches, &config)?;
+            let desc = sub_matches.get_one::<String>("desc").cloned().unwrap();
+            let to_group = sub_matches.get_one::<String>("to-group");
+            let to_table = sub_matches
+                .get_one::<String>("to-table")
+                .filter(|to_table| **to_table != tablename);
+
+            // the task is added to the destination first, a typo there would only fail after
+            if let Some(to_table) = to_table {
+                if with_spinner(|| api.find_table(to_table))?.is_none() {
+                    return Err(Error::TableNotFound);
+                }
+            }
+
+            let previous = with_spinner(|| api.find_task(&tablename, &desc))?;
+            let res = match to_table {
+                // across tables the task is recreated in the destination
+                Some(to_table) => {
+                    let due = previous.due.map(|due| Due(due).to_string());
+                    let mut opts_map = previous.body(&due);
+                    if let Some(group) = to_group {
+                        opts_map.insert("group", group);
+                    }
+
+                    let op = Op::Move {
+                        from: tablename.clone(),
+                        to: to_table.clone(),
+                        previous: previous.clone(),
+                    };
+                    let res = with_spinner(|| api.move_task(tablename, to_table.clone(), opts_map));
+                    if let Ok(res) = &res {
+                        record_op(&config, res.as_ref(), op);
+                    }
+                    res
+                }
+                // within a table only the group changes
+                None => {
+                    let mut opts_map: HashMap<&str, &str> = HashMap::new();
+                    if let Some(group) = to_group {
+                        opts_map.insert("group", group);
+                    }
+
+                    let op = Op::Update {
+                        table: tablename.clone(),
+                        description: desc.clone(),
+                        previous,
+                    };
+                    let res = with_spinner(|| api.update_task(tablename, desc, opts_map));
+                    if let Ok(res) = &res {
+                        record_op(&config, res.as_ref(), op);
+                    }
+                    res
+                }
+            };
+
+            match res {
+                Ok(res) => {
+                    log::info!("Successfully moved the task and received response");
+                    print_response(res.as_ref(), output)?;
+                }
+                Err(err) => {
+                    log::error!("Error occurred while moving task: {:?}", err);
+                    return Err(err);
+                }
+            }
+        }
+        Some(("done", sub_matches)) => {
+            let tablename = resolve_tablename(sub_matches, &config)?;
+            // the recurrences are only known from the tasks, a failed fetch just skips them
+            let tasks = with_spinner(|| api.fetch_tasks(&tablename))
+                .map(|tasks| tasks.res)
+                .unwrap_or_default();
+
+            let mut report = BatchReport::default();
+            for desc in sub_matches.get_many::<String>("desc").unwrap() {
+                let task = tasks.iter().find(|task| &task.description == desc);
+                // tasks are identified by their description, so a task that repeats is
+                // moved to its next occurrence instead of being completed and added again
+                let res = match task.and_then(GetTaskResponseDetail::next_occurrence) {
+                    Some(next) => {
+                        let mut body = HashMap::new();
+                        body.insert("due", next.as_str());
+                        with_spinner(|| api.update_task(tablename.clone(), desc.clone(), body))
+                    }
+                    None => with_spinner(|| api.complete_task(&tablename, desc)),
+                };
+                if let Err(Error::CompletionUnsupported) = res {
+                    return Err(Error::CompletionUnsupported);
+                }
+                report.record(desc, res);
+            }
+
+            log::info!("Completed {} tasks in {tablename}", report.succeeded());
+            report.print(output);
+        }
+        Some(("config", sub_matches)) => match sub_matches.subcommand() {
+            Some(("set", set_matches)) => {
+                let key = set_matches.get_one::<String>("key").unwrap();
+                let value = set_matches.get_one::<String>("value").cloned().unwrap();
+
+                match key.as_str() {
+                    "default_table" => config.default_table = Some(value),
+                    "timezone" => {
+                        // validate the zone before saving it
+                        value.parse::<Zone>()?;
+                        config.timezone = Some(value)
+                    }
+                    "backend" => config.backend = Some(value),
+                    "retries" => {
+                        config.retries = value.parse().map_err(|_| Error::InvalidConfigValue)?
+                    }
+                    "timeout_secs" => {
+                        config.timeout_secs =
+                            value.parse().map_err(|_| Error::InvalidConfigValue)?
+                    }
+                    "insecure" => {
+                        config.insecure = value.parse().map_err(|_| Error::InvalidConfigValue)?
+                    }
+                    "frontend" => config.frontend = value.parse()?,
+                    "normalize_whitespace" => {
+                        config.normalize_whitespace =
+                            value.parse().map_err(|_| Error::InvalidConfigValue)?
+                    }
+                    _ => unreachable!("The key is validated by clap"),
+                }
+                config.update_config()?;
+
+                log::info!("Successfully set config key {key}");
+                match output {
+                    OutputMode::Human => emit("\x1b[34mConfiguration updated\x1b[0m\n"),
+                    OutputMode::Json => {
+                        emit(&format!("{}\n", serde_json::json!({ "res": "updated" })))
+                    }
+                }
+            }
+            Some(("check", _)) => {
+                let check = check_config_file(Utc::now());
+                log::info!(
+                    "Checked the config file, {} problems found",
+                    check.problems.len()
+                );
+                print_response(&check, output)?;
+                if !check.is_valid() {
+                    return Err(Error::InvalidConfig);
+                }
+            }
+            _ => unreachable!("If you are reading this something really bad happened"),
+        },
+        Some(("profile", sub_matches)) => match sub_matches.subcommand() {
+            Some(("add", add_matches)) => {
+                let name = add_matches.get_one::<String>("name").unwrap();
+                if config.profiles.contains_key(name) {
+                    return Err(Error::ProfileAlreadyExists);
+                }
+
+                let profile = Profile {
+                    backend: add_matches.get_one::<String>("backend").cloned(),
+                    ..Profile::default()
+                };
+                config.profiles.insert(name.clone(), profile);
+                config.update_config()?;
+
+                log::info!("Added profile {name}");
+                match output {
+                    OutputMode::Human => emit(&format!(
+                        "\x1b[34mProfile added, switch to it with `rsm profile use {name}`\x1b[0m\n"
+                    )),
+                    OutputMode::Json => {
+                        emit(&format!("{}\n", serde_json::json!({ "res": "added" })))
+                    }
+                }
+            }
+            Some(("use", use_matches)) => {
+                let name = use_matches.get_one::<String>("name").unwrap();
+                config.use_profile(name)?;
+                config.update_config()?;
+
+                log::info!("Switched to profile {name}");
+                match output {
+                    OutputMode::Human => emit(&format!("\x1b[34mUsing profile {name}\x1b[0m\n")),
+                    OutputMode::Json => {
+                        emit(&format!("{}\n", serde_json::json!({ "res": "switched" })))
+                    }
+                }
+            }
+            Some(("list", _)) => {
+                let profiles = config
+                    .profiles
+                    .iter()
+                    .map(|(name, profile)| (name, profile, *name == config.active_profile));
+                match output {
+                    OutputMode::Human => {
+                        for (name, profile, active) in profiles {
+                            let marker = if active { "*" } else { " " };
+                            let backend = profile.backend.as_deref().unwrap_or("default backend");
+                            emit(&format!("{marker} {name} ({backend})\n"));
+                        }
+                    }
+                    OutputMode::Json => {
+                        let profiles: Vec<_> = profiles
+                            .map(|(name, profile, active)| {
+                                serde_json::json!({
+                                    "name": name,
+                                    "backend": profile.backend,
+                                    "username": profile.username,
+                                    "active": active,
+                                    "logged_in": profile.token.is_some(),
+                                })
+                            })
+                            .collect();
+                        emit(&format!("{}\n", serde_json::json!(profiles)));
+                    }
+                }
+            }
+            _ => unreachable!("If you are reading this something really bad happened"),
+        },
         Some(("clear", sub_matches)) => {
-            let tablename = sub_matches
-                .get_one::<String>("tablename")
-                .map(|s| s.to_owned())
-                .unwrap();
+            let tablename = resolve_tablename(sub_matches, &config)?;
+
+            let question = match sub_matches.get_flag("completed") {
+                true => {
+                    format!("Are you sure you want to clear the completed tasks of '{tablename}'?")
+                }
+                false => format!("Are you sure you want to clear '{tablename}'?"),
+            };
+            if !sub_matches.get_flag("yes") && !confirm(&question)? {
+                return Ok(());
+            }
+
+            if sub_matches.get_flag("completed") {
+                let completed: Vec<GetTaskResponseDetail> =
+                    with_spinner(|| api.fetch_tasks(&tablename))?
+                        .res
+                        .into_iter()
+                        .filter(|task| task.completed)
+                        .collect();
+
+                let descs: Vec<String> = completed
+                    .iter()
+                    .map(|task| task.description.clone())
+                    .collect();
+                let report = with_spinner(|| api.remove_tasks(&tablename, &descs));
+                record_removed(&config, &tablename, completed, &report);
 
-            match api.clear_table(tablename) {
+                log::info!(
+                    "Removed {} completed tasks from {tablename}",
+                    report.succeeded()
+                );
+                report.print(output);
+                return Ok(());
+            }
+
+            // the snapshot is only needed by undo, the clear goes on without it
+            let op = with_spinner(|| api.fetch_tasks(&tablename))
+                .ok()
+                .map(|tasks| Op::Remove {
+                    table: tablename.clone(),
+                    tasks: tasks.res,
+                });
+
+            match with_spinner(|| api.clear_table(tablename)) {
                 Ok(res) => {
                     log::info!("Successfully sent DELETE clear request and received response");
-                    res.print();
+                    if let Some(op) = op {
+                        record_op(&config, res.as_ref(), op);
+                    }
+                    print_response(res.as_ref(), output)?;
                 }
                 Err(err) => {
                     log::error!("Error occurred while adding task: {:?}", err);
@@ -660,53 +2100,242 @@ fn main() -> Result<()> {
                 }
             }
         }
+        Some(("show", sub_matches)) => {
+            let tablename = resolve_tablename(sub_matches, &config)?;
+            let desc = sub_matches.get_one::<String>("desc").unwrap();
+
+            let task = match with_spinner(|| api.find_task(&tablename, desc)) {
+                Err(Error::TaskNotFound) => {
+                    with_spinner(|| api.find_task_by_name(&tablename, desc))?
+                }
+                task => task?,
+            };
+
+            log::info!("Successfully fetched task '{}'", task.description);
+            match output {
+                OutputMode::Human => emit(&task.to_string()),
+                OutputMode::Json => emit(&format!(
+                    "{}\n",
+                    serde_json::to_string(&task).map_err(|_| Error::RsmFailed)?
+                )),
+            }
+        }
+        Some(("search", sub_matches)) => {
+            let query = sub_matches.get_one::<String>("query").unwrap();
+            let tablenames = match sub_matches.get_one::<String>("table") {
+                Some(tablename) => vec![tablename.to_owned()],
+                None => match with_spinner(|| api.get_tasks(None, HashMap::new()))? {
+                    TaskListing::Tables(tables) => {
+                        tables.res.into_iter().map(|table| table.name).collect()
+                    }
+                    listing => return print_response(&listing, output),
+                },
+            };
+
+            let mut found = vec![];
+            for tablename in tablenames {
+                let mut tasks = with_spinner(|| api.fetch_tasks(&tablename))?;
+                tasks.retain_matching(query);
+                if !tasks.res.is_empty() {
+                    found.push((tablename, tasks));
+                }
+            }
+            log::info!("Found tasks matching '{query}' in {} tables", found.len());
+
+            match output {
+                OutputMode::Human if found.is_empty() => {
+                    emit(&format!("No tasks match '{query}'\n"));
+                }
+                // the owning table is only worth showing when more tables were searched
+                OutputMode::Human if sub_matches.contains_id("table") => found[0].1.print(),
+                OutputMode::Human => {
+                    for (tablename, tasks) in &found {
+                        emit(&format!("\x1b[34m{tablename}\x1b[0m\n"));
+                        tasks.print();
+                    }
+                }
+                OutputMode::Json => {
+                    let found: Vec<serde_json::Value> = found
+                        .iter()
+                        .map(|(tablename, tasks)| {
+                            serde_json::json!({ "table": tablename, "tasks": tasks.res })
+                        })
+                        .collect();
+                    emit(&format!("{}\n", serde_json::json!(found)));
+                }
+            }
+        }
+        Some(("stats", sub_matches)) => {
+            let tablename = resolve_tablename(sub_matches, &config)?;
+            let stats = with_spinner(|| api.fetch_tasks(&tablename))?.stats(server_now());
+
+            log::info!("Counted the {} tasks of '{tablename}'", stats.total);
+            print_response(&stats, output)?;
+        }
+        Some(("notify", sub_matches)) => {
+            let window =
+                Duration::minutes(i64::from(*sub_matches.get_one::<u32>("window").unwrap()));
+            let listing = with_spinner(|| api.get_tasks(None, HashMap::new()))?;
+            let TaskListing::Tables(tables) = &listing else {
+                return print_response(&listing, output);
+            };
+
+            let now = Utc::now();
+            let server_time = server_now();
+            let mut notified = NotifiedLog::load(&config);
+            let mut report = BatchReport::default();
+            // only the tables with due can have overdue tasks
+            for table in tables.res.iter().filter(|table| table.has_due) {
+                for task in with_spinner(|| api.fetch_tasks(&table.name))?.res {
+                    let key = format!("{}/{}", table.name, task.description);
+                    if task.completed
+                        || !task.is_overdue(server_time)
+                        || !notified.should_notify(&key, now, window)
+                    {
+                        continue;
+                    }
+
+                    match send_overdue_notification(&table.name, &task) {
+                        Ok(()) => {
+                            report.success(&key);
+                            notified.mark(key, now);
+                        }
+                        Err(err) => report.failure(&key, err.to_string()),
+                    }
+                }
+            }
+            notified.save(now, window)?;
+
+            log::info!("Sent {} overdue notifications", report.succeeded());
+            report.print(output);
+        }
+        Some(("whoami", _)) => {
+            let Some(token) = config.token.clone().map(Token::from) else {
+                if output == OutputMode::Human {
+                    eprintln!("Not logged in");
+                }
+                return Err(Error::NoAuth);
+            };
+            let expires = token.expires();
+
+            match output {
+                OutputMode::Human => {
+                    let username = config.username.as_deref().unwrap_or("unknown");
+                    let session = match expires {
+                        Some(expires) => describe_expiry(expires - Utc::now()),
+                        None => "does not expire".to_owned(),
+                    };
+                    emit(&format!("\x1b[34musername\x1b[0m  {username}\n"));
+                    emit(&format!("\x1b[34msession\x1b[0m   {session}\n"));
+                }
+                OutputMode::Json => emit(&format!(
+                    "{}\n",
+                    serde_json::json!({
+                        "username": config.username,
+                        "expires": expires.map(|expires| expires.to_rfc3339()),
+                    })
+                )),
+            }
+        }
+        Some(("tui", _)) => open_tui(&api, &config)?,
+        Some(("undo", _)) => {
+            let mut op_log = OpLog::load(&config);
+            let op = op_log.pop()?;
+
+            if output == OutputMode::Human {
+                emit(&format!("Undoing: {op}\n"));
+            }
+            match with_spinner(|| op.undo(&api)) {
+                Ok(report) => {
+                    report.print(output);
+                    let undone = op.to_string();
+                    // keep what couldn't be undone so the undo can be retried
+                    if let Some(remaining) = op.remaining(&report) {
+                        log::error!("Failed to undo: {remaining}");
+                        op_log.push(remaining);
+                        return Err(Error::FailedToUndo);
+                    }
+                    log::info!("Undid: {undone}");
+                }
+                Err(err) => {
+                    log::error!("Error occurred while undoing: {:?}", err);
+                    // keep the operation so the undo can be retried
+                    op_log.push(op);
+                    return Err(err);
+                }
+            }
+        }
+        // without a subcommand the configured frontend is opened, the cli one never gets here
+        None => open_tui(&api, &config)?,
         _ => unreachable!("If you are reading this something really bad happened"),
     }
 
     Ok(())
 }
 
+/// Opens the terminal interface, it needs a terminal to draw on and to read the keys from
+///
+/// # Args
+/// - api: struct `Api` that represents the interface to the api
+/// - config: struct `Config` that represents the config management
+fn open_tui(api: &Api, config: &Config) -> Result<()> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(Error::NoTerminal);
+    }
+    rsm::tui::run(api, config)
+}
+
 /// If it is the first time running the app for the user this function handles his login or signup
 ///
 /// # Args
 /// - api: struct `Api` that represents the interface to the api
 /// - config: struct `Config` that represents the config management
-fn show_first_run_prompt(api: &Api, config: &mut Config) -> Result<()> {
-    println!("\x1b[34mWelcome to RsMember!\x1b[0m\n");
+/// - output: the output mode, the banners are only printed in human mode
+fn show_first_run_prompt(api: &Api, config: &mut Config, output: OutputMode) -> Result<()> {
+    if output == OutputMode::Human {
+        println!("\x1b[34mWelcome to RsMember!\x1b[0m\n");
+    }
 
-    print!("do you already have a key([yes]/no): ");
-    std::io::stdout().flush().map_err(|_| Error::RsmFailed)?;
+    eprint!("do you already have a key([yes]/no): ");
+    io::stderr().flush().map_err(|_| Error::RsmFailed)?;
     let choice = get_user_choice().map_err(|_| Error::RsmFailed)?;
 
     match choice {
         // send login req
         Choice::Yes => {
-            let (key, token) = login(api).map_err(|e| {
+            let (key, token) = login(api, output).map_err(|e| {
                 log::error!("{e:?}");
                 e
             })?;
-            config.key = Some(key.0.replace("\n", ""));
+            config.key = Some(key.0.replace('\n', ""));
             let token: String = token.into();
-            config.token = Some(token.replace("\n", ""));
+            config.token = Some(token.replace('\n', ""));
 
             log::info!("successful login");
             Ok(())
         }
-        // send signup req
+        // send signup req, then login with the key it returned
         Choice::No => {
-            signup(api).map_err(|e| {
+            let (username, key) = signup(api, output).map_err(|e| {
                 log::error!("{e:?}");
                 e
             })?;
-            println!("Log in:");
+            config.username = Some(username);
 
-            let (key, token) = login(api).map_err(|e| {
+            let (key, token) = match key {
+                Some(key) => login_with_key(api, key.0, output),
+                None => {
+                    eprintln!("Log in:");
+                    login(api, output)
+                }
+            }
+            .map_err(|e| {
                 log::error!("{e:?}");
                 e
             })?;
-            config.key = Some(key.0.replace("\n", ""));
+            config.key = Some(key.0.replace('\n', ""));
             let token: String = token.into();
-            config.token = Some(token.replace("\n", ""));
+            config.token = Some(token.replace('\n', ""));
 
             log::info!("successful signup and login");
             Ok(())
@@ -714,6 +2343,107 @@ fn show_first_run_prompt(api: &Api, config: &mut Config) -> Result<()> {
     }
 }
 
+/// Returns the tablename passed to a subcommand, falling back to the default table
+/// set in the config when it is omitted
+///
+/// # Args
+/// - sub_matches: the matches of the subcommand
+/// - config: struct `Config` that represents the config management
+fn resolve_tablename(sub_matches: &clap::ArgMatches, config: &Config) -> Result<String> {
+    match sub_matches.get_one::<String>("tablename") {
+        Some(tablename) => Ok(tablename.to_owned()),
+        None => config.default_table.clone().ok_or_else(|| {
+            eprintln!(
+                "No table specified, pass one or set a default with `rsm config set default_table <tablename>`"
+            );
+            Error::NoDefaultTable
+        }),
+    }
+}
+
+/// Returns a copy of the given tables with the number of tasks of each one,
+/// this needs a request for every table
+///
+/// # Args
+/// - api: struct `Api` that represents the interface to the api
+/// - tables: the tables to count the tasks of
+fn with_task_counts(
+    api: &Api,
+    tables: &TableCharacteristicsResponse,
+) -> Result<TableCharacteristicsResponse> {
+    let mut tables = tables.clone();
+    for table in tables.res.iter_mut() {
+        table.tasks = match with_spinner(|| api.get_tasks(Some(&table.name), HashMap::new()))? {
+            TaskListing::Tasks(tasks) => Some(tasks.res.len()),
+            _ => None,
+        };
+    }
+    Ok(tables)
+}
+
+/// Records the removal of the tasks of a batch that the server accepted in the op log, as a
+/// single operation so that they are undone together
+///
+/// # Args
+/// - config: struct `Config` that represents the config management
+/// - tablename: the table the tasks were removed from
+/// - tasks: the snapshots of the tasks before the removal, the ones not removed are skipped
+/// - report: the outcomes of the removals
+fn record_removed(
+    config: &Config,
+    tablename: &str,
+    tasks: Vec<GetTaskResponseDetail>,
+    report: &BatchReport,
+) {
+    let removed: Vec<GetTaskResponseDetail> = tasks
+        .into_iter()
+        .filter(|task| {
+            report
+                .items()
+                .iter()
+                .any(|item| item.ok && item.item == task.description)
+        })
+        .collect();
+    if !removed.is_empty() {
+        OpLog::load(config).push(Op::Remove {
+            table: tablename.to_owned(),
+            tasks: removed,
+        });
+    }
+}
+
+/// Prints the day and time a due given to a successful add or update was resolved to, e.g.
+/// `Due: 2024-06-02 18:30 Europe/Rome (tomorrow)`, so that a time already past today isn't a surprise
+///
+/// # Args
+/// - res: the response of the operation
+/// - due: the due given to the operation
+/// - output: the output mode, nothing is printed in json mode
+fn print_resolved_due(res: &dyn FormattedResponse, due: Option<&Due>, output: OutputMode) {
+    if let Some(due) = due {
+        if output == OutputMode::Human && res.as_any().is::<SuccessfulResponse>() {
+            emit(&format!("Due: {}\n", due.describe()));
+        }
+    }
+}
+
+/// How many tasks a page of `list` has when only `--page` is given
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Environment variable providing the password when there is no terminal to ask it on
+const PASSWORD_ENV: &str = "RSM_PASSWORD";
+
+/// Asks the password without echoing it, the password is never taken from the arguments
+/// so it doesn't end up in the shell history. Without a terminal (e.g. in CI) it is taken
+/// from `RSM_PASSWORD` instead
+fn read_password() -> Result<String> {
+    if !io::stdin().is_terminal() {
+        return env::var(PASSWORD_ENV).map_err(|_| Error::NoPassword);
+    }
+
+    rpassword::prompt_password("password: ").map_err(|_| Error::RsmFailed)
+}
+
 /// Wrapper struct that represents an api key
 struct Key(String);
 
@@ -723,12 +2453,52 @@ impl From<String> for Key {
     }
 }
 
+/// Describes how long the session has left, e.g. "expires in 2h 13m" or "expired 5m ago"
+///
+/// # Args
+/// - left: the time until the expiry, negative once expired
+fn describe_expiry(left: Duration) -> String {
+    let minutes = left.num_minutes().abs();
+    let amount = match (minutes / (60 * 24), minutes / 60 % 24, minutes % 60) {
+        (0, 0, m) => format!("{m}m"),
+        (0, h, m) => format!("{h}h {m}m"),
+        (d, h, _) => format!("{d}d {h}h"),
+    };
+
+    if left > Duration::zero() {
+        format!("expires in {amount}")
+    } else {
+        format!("expired {amount} ago")
+    }
+}
+
+/// Logs in again with the saved key when the session expired, without a saved key
+/// the user has to log in again
+///
+/// # Args
+/// - api: struct `Api` without a token
+/// - config: struct `Config` where the new token is stored
+fn refresh_token(api: &Api, config: &mut Config) -> Result<()> {
+    let key = config.key.clone().ok_or(Error::NoAuth)?;
+
+    let (res, session) = with_spinner(|| api.post_login(&key))?;
+    if !res.as_any().is::<SuccessfulResponse>() {
+        res.print();
+        return Err(Error::LoginFail);
+    }
+
+    log::info!("The session expired, logged in again with the saved key");
+    config.token = session.map(|session| session.to_cookie_header());
+    Ok(())
+}
+
 /// Handles the login logic
 ///
 /// # Args
 /// - api: struct `Api` that represents the interface to the api
-fn login(api: &Api) -> Result<(Key, Token)> {
-    println!("Please input your key");
+/// - output: the output mode, the banners are only printed in human mode
+fn login(api: &Api, output: OutputMode) -> Result<(Key, Token)> {
+    eprintln!("Please input your key");
 
     let mut key = String::new();
     io::stdin()
@@ -736,57 +2506,67 @@ fn login(api: &Api) -> Result<(Key, Token)> {
         .map_err(|_| Error::RsmFailed)?;
 
     // prettier output
-    println!("");
-    let handle = terminal_spinners::SpinnerBuilder::new()
-        .spinner(&terminal_spinners::DOTS)
-        .text("Signing up...")
-        .start();
-    let res = api.post_login(&key)?;
-    handle.done();
+    eprintln!();
+    login_with_key(api, key, output)
+}
+
+/// Logs in with an already known key
+///
+/// # Args
+/// - api: struct `Api` that represents the interface to the api
+/// - key: the key of the account
+/// - output: the output mode, the banners are only printed in human mode
+fn login_with_key(api: &Api, key: String, output: OutputMode) -> Result<(Key, Token)> {
+    let res = with_spinner(|| api.post_login(&key))?;
 
     let res_type = &res.0.as_any();
     if res_type.is::<ErrorResponse>() {
         res.0.print();
         return Err(Error::LoginFail);
     } else if res_type.is::<SuccessfulResponse>() {
-        res.0.print();
-        println!("\x1b[34mWelcome to this machine!\x1b[0m\n");
+        print_response(res.0.as_ref(), output)?;
+        if output == OutputMode::Human {
+            println!("\x1b[34mWelcome to this machine!\x1b[0m\n");
+        }
     }
-    Ok((key.into(), res.1.into()))
+    Ok((key.into(), res.1.map(Token::from).unwrap_or_default()))
 }
 
-/// Handles the signup logic
+/// Handles the signup logic, returns the username and, if the server sent it back, the key
+/// of the new account
 ///
 /// # Args
 /// - api: struct `Api` that represents the interface to the api
-fn signup(api: &Api) -> Result<()> {
-    println!("Create Account:");
-    print!("username: ");
-    io::stdout().flush().map_err(|_| Error::RsmFailed)?;
+/// - output: the output mode, the banners are only printed in human mode
+fn signup(api: &Api, output: OutputMode) -> Result<(String, Option<Key>)> {
+    eprintln!("Create Account:");
+    eprint!("username: ");
+    io::stderr().flush().map_err(|_| Error::RsmFailed)?;
 
     let mut username = String::new();
     io::stdin()
         .read_line(&mut username)
         .map_err(|_| Error::RsmFailed)?;
 
-    let password = rpassword::prompt_password("password: ").map_err(|_| Error::RsmFailed)?;
+    let password = read_password()?;
 
     // prettier output
-    println!("");
-    let handle = terminal_spinners::SpinnerBuilder::new()
-        .spinner(&terminal_spinners::DOTS)
-        .text("Signing up...")
-        .start();
-    let res = api.post_signup(&username, &password)?;
-    handle.done();
+    eprintln!();
+    let res = with_spinner(|| api.post_signup(&username, &password))?;
 
     let res_type = &res.as_any();
     if res_type.is::<ErrorResponse>() {
         res.print();
         return Err(Error::FirstRunFailed);
-    } else if res_type.is::<SuccessfulResponse>() {
-        println!("Account creation successful, you can now log in!");
-        res.print();
     }
-    Ok(())
+
+    let key = res_type
+        .downcast_ref::<SuccessfulResponse>()
+        .and_then(|success| success.quoted_value())
+        .map(|key| Key(key.to_owned()));
+    if output == OutputMode::Human {
+        println!("Account creation successful!");
+    }
+    print_response(res.as_ref(), output)?;
+    Ok((username.trim().to_owned(), key))
 }
@@ -1,26 +1,67 @@
-/// # Parsers Module
-///
-/// This module contains custom parsers for specific data types used in the application.
-/// _Those parsers are build for clap_
-///
-/// ## Custom Parsers
-///
-/// - `LineRange`: Parses a string representing a range of lines.
-/// - `Due`: Parses a string representing a due date and time.
-///
-/// ## Custom Parsers
-///
-/// ### LineRange
-///
-/// Parses a string representing a range of lines in the format `<start>..<end>`.
-///
-/// ### Due
-///
-/// Parses a string representing a due date and time. The format can be either just the time (`HH:MM`) or date and time (`YYYY-MM-DD HH:MM`).
+//! # Parsers Module
+//!
+//! This module contains custom parsers for specific data types used in the application.
+//! _Those parsers are build for clap_
+//!
+//! ## Custom Parsers
+//!
+//! - `LineRange`: Parses a string representing a range of lines.
+//! - `Due`: Parses a string representing a due date and time.
+//! - `QueryParam`: Parses a `key=value` query parameter.
+//! - `Recurrence`: Parses how often a task repeats.
+//! - `SortKey`: Parses the key the tasks are sorted by.
+//! - `TaskLine`: Parses a task of a batch import.
+//!
+//! ## Custom Parsers
+//!
+//! ### LineRange
+//!
+//! Parses a string representing a range of lines in the format `<start>..<end>`.
+//!
+//! ### Due
+//!
+//! Parses a string representing a due date and time. The format can be either just the time (`HH:MM`) or date and time (`YYYY-MM-DD HH:MM`).
+//! The date can also be `today` or `tomorrow` (e.g. `tomorrow 09:00`), `today` with a time already
+//! past is an error, or a weekday (`friday 14:00` or `fri 14:00`) for its next occurrence.
+//! An offset from now is accepted too: `+30m`, `+2h` or `+1d`. Times can be in the 12-hour
+//! format as well (`2:30pm` or `2:30 PM`), and any time can have seconds (`14:30:15`).
+//! An ISO 8601 timestamp (`2024-06-02T18:30` or `2024-06-02T18:30:15`) is taken as is, so that
+//! the output of other tools can be passed.
+//! An explicit date already past is accepted with a warning in the log, `is_past` lets the
+//! callers refuse it instead.
+//! The parsed value is kept as a `NaiveDateTime`, the string sent to the backend is derived from it.
+//! The input is interpreted in the configured timezone and stored in UTC, a time skipped or
+//! repeated in that zone by a daylight saving change is an error.
+//! `describe` shows it back in the configured timezone with the day it falls on, e.g.
+//! `2024-06-02 18:30 Europe/Rome (tomorrow)`.
+//!
+//! ### QueryParam
+//!
+//! Parses a query parameter in the format `<key>=<value>`, the key can only contain
+//! alphanumeric characters and underscores.
+//!
+//! ### Recurrence
+//!
+//! Parses how often a task repeats, either as a keyword (`hourly`, `daily`, `weekly`, `monthly`)
+//! or as an amount followed by a unit (`6h`, `2d`, `1w`, `3mo`). The string sent to the backend
+//! is always the second form.
+//!
+//! ### SortKey
+//!
+//! Parses the key the tasks are sorted by, `due`, `group` or `description`, optionally followed
+//! by a direction (`due:desc`, `group:asc`), ascending by default.
+//!
+//! ### TaskLine
+//!
+//! Parses a line of a batch import in the format `<name> | <due> | <group>`, the due and the
+//! group are optional and can be left empty (`buy milk | | home`). The due is parsed as a `Due`.
 
 use std::ops::RangeInclusive;
 
-use chrono::{Duration, Local, NaiveTime};
+use chrono::format::ParseErrorKind;
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+
+use crate::utils::timezone::{server_now, zone, Zone};
 
 // -- Custom Parsers
 #[derive(Clone, Debug)]
@@ -50,51 +91,660 @@ impl std::str::FromStr for LineRange {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct QueryParam {
+    pub key: String,
+    pub value: String,
+}
+
+impl std::str::FromStr for QueryParam {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or("Incorrect input format. Expected format: <key>=<value>")?;
+
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err("Invalid key, use only letters, digits and underscores");
+        }
+
+        let value = value.trim();
+        if value.is_empty() {
+            return Err("Empty value");
+        }
+
+        Ok(QueryParam {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecurrenceUnit {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl RecurrenceUnit {
+    fn suffix(&self) -> &'static str {
+        match self {
+            RecurrenceUnit::Hour => "h",
+            RecurrenceUnit::Day => "d",
+            RecurrenceUnit::Week => "w",
+            RecurrenceUnit::Month => "mo",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            RecurrenceUnit::Hour => "hour",
+            RecurrenceUnit::Day => "day",
+            RecurrenceUnit::Week => "week",
+            RecurrenceUnit::Month => "month",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Recurrence {
+    pub every: u32,
+    pub unit: RecurrenceUnit,
+}
+
+impl Recurrence {
+    /// Returns the due of the occurrence after the one due at `due`
+    pub fn next_due(&self, due: NaiveDateTime) -> Option<NaiveDateTime> {
+        let every = i64::from(self.every);
+        match self.unit {
+            RecurrenceUnit::Hour => due.checked_add_signed(Duration::hours(every)),
+            RecurrenceUnit::Day => due.checked_add_signed(Duration::days(every)),
+            RecurrenceUnit::Week => due.checked_add_signed(Duration::weeks(every)),
+            RecurrenceUnit::Month => due.checked_add_months(Months::new(self.every)),
+        }
+    }
+
+    /// Describes the recurrence for humans, e.g. "every day" or "every 2 weeks"
+    pub fn describe(&self) -> String {
+        match self.every {
+            1 => format!("every {}", self.unit.name()),
+            every => format!("every {} {}s", every, self.unit.name()),
+        }
+    }
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.every, self.unit.suffix())
+    }
+}
+
+impl std::str::FromStr for Recurrence {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let keyword_unit = match s.as_str() {
+            "hourly" => Some(RecurrenceUnit::Hour),
+            "daily" => Some(RecurrenceUnit::Day),
+            "weekly" => Some(RecurrenceUnit::Week),
+            "monthly" => Some(RecurrenceUnit::Month),
+            _ => None,
+        };
+        if let Some(unit) = keyword_unit {
+            return Ok(Recurrence { every: 1, unit });
+        }
+
+        let split = s
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or("Missing unit, use one of: h, d, w, mo")?;
+        let (every, unit) = s.split_at(split);
+        let every: u32 = every.parse().map_err(|_| "Invalid amount")?;
+        if every == 0 {
+            return Err("The amount has to be at least 1");
+        }
+
+        let unit = match unit {
+            "h" => RecurrenceUnit::Hour,
+            "d" => RecurrenceUnit::Day,
+            "w" => RecurrenceUnit::Week,
+            "mo" => RecurrenceUnit::Month,
+            _ => return Err("Invalid unit, use one of: h, d, w, mo"),
+        };
+
+        Ok(Recurrence { every, unit })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortField {
+    Due,
+    Group,
+    Description,
+}
+
+impl SortField {
+    /// The name of the field for the `sort_by` query param
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortField::Due => "due",
+            SortField::Group => "group",
+            SortField::Description => "description",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, direction) = s.trim().split_once(':').unwrap_or((s.trim(), "asc"));
+
+        let field = match field.to_lowercase().as_str() {
+            "due" => SortField::Due,
+            "group" => SortField::Group,
+            "description" => SortField::Description,
+            _ => return Err("Invalid sort key, use due, group or description"),
+        };
+        let descending = match direction.to_lowercase().as_str() {
+            "asc" => false,
+            "desc" => true,
+            _ => return Err("Invalid sort direction, use asc or desc"),
+        };
+
+        Ok(SortKey { field, descending })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TaskLine {
+    pub description: String,
+    pub due: Option<Due>,
+    pub group: Option<String>,
+}
+
+impl std::str::FromStr for TaskLine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split('|').map(str::trim).collect();
+        if fields.len() > 3 {
+            return Err("Too many fields, expected format: <name> | <due> | <group>".to_owned());
+        }
+
+        let description = fields[0].to_owned();
+        if description.is_empty() {
+            return Err("The task name is empty".to_owned());
+        }
+
+        let due = match fields.get(1) {
+            Some(due) if !due.is_empty() => {
+                Some(due.parse::<Due>().map_err(|e| format!("{e}: '{due}'"))?)
+            }
+            _ => None,
+        };
+
+        let group = fields
+            .get(2)
+            .filter(|group| !group.is_empty())
+            .map(|group| group.to_string());
+
+        Ok(TaskLine {
+            description,
+            due,
+            group,
+        })
+    }
+}
+
+/// The format the backend expects the due in
+const BACKEND_DUE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
 #[derive(Clone, Debug, Default)]
-pub struct Due(pub String);
+pub struct Due(pub NaiveDateTime);
+
+impl Due {
+    /// Returns the due as the user reads it, in the configured timezone (which is named) and
+    /// with the day it falls on when it is within a week, e.g.
+    /// `2024-06-02 18:30 Europe/Rome (tomorrow)`
+    pub fn describe(&self) -> String {
+        self.describe_at(zone(), server_now())
+    }
+
+    /// `describe` with the day relative to `now` (as the server stores it) in `zone`
+    fn describe_at(&self, zone: Zone, now: NaiveDateTime) -> String {
+        let due = zone.from_server(self.0);
+        let day = match (due.date() - zone.from_server(now).date()).num_days() {
+            0 => " (today)".to_owned(),
+            1 => " (tomorrow)".to_owned(),
+            2..=6 => format!(" ({})", due.format("%A")),
+            _ => String::new(),
+        };
+        format!("{} {zone}{day}", due.format("%Y-%m-%d %H:%M"))
+    }
+
+    /// Whether the due is already past, only an explicit date can be
+    pub fn is_past(&self) -> bool {
+        self.is_past_at(server_now())
+    }
+
+    /// Whether the due is already past at `now` (as the server stores it)
+    pub fn is_past_at(&self, now: NaiveDateTime) -> bool {
+        self.0 < now
+    }
+}
+
+impl std::fmt::Display for Due {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.format(BACKEND_DUE_FORMAT))
+    }
+}
+
 impl std::str::FromStr for Due {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.trim().split_whitespace().collect();
-        if parts.len() > 2 || parts.len() <= 0 {
+        Due::parse_at(s, zone(), server_now())
+    }
+}
+
+impl Due {
+    /// Parses a due typed in `zone` at `now` (as the server stores it), `from_str` parses it
+    /// in the configured zone at the current time
+    fn parse_at(s: &str, zone: Zone, now: NaiveDateTime) -> Result<Due, &'static str> {
+        let mut parts: Vec<String> = s.split_whitespace().map(str::to_owned).collect();
+        // a spaced `am`/`pm` belongs to the time before it
+        if parts.len() > 1 && is_meridiem(parts.last().unwrap()) {
+            let meridiem = parts.pop().unwrap();
+            parts.last_mut().unwrap().push_str(&meridiem);
+        }
+        if parts.len() > 2 || parts.is_empty() {
             return Err("Invalid date and time format");
         }
 
-        if parts.len() == 1 {
-            // only time is provided
-            let time_raw = parts.get(0).map_or("", |s| s).trim();
-            if time_raw.split(":").collect::<Vec<&str>>().len() != 2 || time_raw.is_empty() {
-                return Err("Invalid time");
-            }
+        let server_now = now;
+        let now = zone.from_server(server_now);
 
-            let time = NaiveTime::parse_from_str(time_raw, "%H:%M")
-                .map_err(|_| Self::Err::from("Invalid time format"))?;
+        if parts.len() == 1 && parts[0].contains('T') {
+            // an ISO 8601 timestamp, e.g. from another tool
+            let due = parse_iso(&parts[0])?;
+            warn_if_past(due, now);
+            return Ok(Due(zone.to_server(due)?));
+        }
 
-            let now = Local::now().time();
-            let today = Local::now().naive_local();
+        if let Some(offset) = parts[0].strip_prefix('+') {
+            // an offset from now, e.g. `+2h`
+            if parts.len() != 1 {
+                return Err("Invalid offset, expected e.g. +30m, +2h or +1d");
+            }
+            let due = server_now
+                .checked_add_signed(parse_offset(offset)?)
+                .ok_or("Offset too large")?;
+            let due = due.with_second(0).and_then(|due| due.with_nanosecond(0));
+            return due.map(Due).ok_or("Invalid offset");
+        }
+
+        if parts.len() == 1 {
+            // only time is provided
+            let time = parse_time(&parts[0])?;
 
             // if the time is in the past then the date has to be tomorrow
-            let date = if time < now {
-                (today + Duration::days(1)).date()
+            let date = if time < now.time() {
+                (now + Duration::days(1)).date()
             } else {
-                today.date()
-            }
-            .to_string();
+                now.date()
+            };
 
-            Ok(Due(format!("{date}T{time_raw}:00")))
+            Ok(Due(zone.to_server(date.and_time(time))?))
         } else {
-            // date and time are provided
-            let date_raw = parts.get(0).map_or("", |s| s).trim();
-            if date_raw.split("-").collect::<Vec<&str>>().len() != 3 || date_raw.is_empty() {
-                return Err("Invalid date");
-            }
+            // date and time are provided, the date may be a keyword
+            let time = parse_time(&parts[1])?;
 
-            let time_raw = parts.get(1).map_or("", |s| s).trim();
-            if time_raw.split(":").collect::<Vec<&str>>().len() != 2 || time_raw.is_empty() {
-                return Err("Invalid time");
-            }
-            Ok(Due(format!("{date_raw}T{time_raw}:00")))
+            let date = match parts[0].trim().to_lowercase().as_str() {
+                "today" => {
+                    if time < now.time() {
+                        return Err("The time is already past for today");
+                    }
+                    now.date()
+                }
+                "tomorrow" => (now + Duration::days(1)).date(),
+                date_raw => match date_raw.parse::<Weekday>() {
+                    Ok(weekday) => next_weekday(now, weekday, time),
+                    Err(_) => parse_date(date_raw)?,
+                },
+            };
+
+            let due = date.and_time(time);
+            warn_if_past(due, now);
+
+            Ok(Due(zone.to_server(due)?))
         }
     }
 }
+
+/// Logs a warning when an explicit due is already past, it is most likely a typo but it may
+/// be on purpose (e.g. to log a task already late), refusing it is left to the callers
+fn warn_if_past(due: NaiveDateTime, now: NaiveDateTime) {
+    if due < now {
+        log::warn!("The due {} is in the past", due.format("%Y-%m-%d %H:%M"));
+    }
+}
+
+/// Returns the date of the next `weekday` at `time` from `now`, today only counts
+/// when `time` is still to come
+fn next_weekday(now: NaiveDateTime, weekday: Weekday, time: NaiveTime) -> NaiveDate {
+    let mut days_ahead =
+        (7 + weekday.num_days_from_monday() - now.weekday().num_days_from_monday()) % 7;
+    if days_ahead == 0 && time < now.time() {
+        days_ahead = 7;
+    }
+    (now + Duration::days(i64::from(days_ahead))).date()
+}
+
+/// Parses an offset made of a positive amount and a unit, `m` for minutes, `h` for hours
+/// or `d` for days
+fn parse_offset(offset_raw: &str) -> Result<Duration, &'static str> {
+    let split = offset_raw
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or("Missing offset unit, use m, h or d")?;
+    let (amount, unit) = offset_raw.split_at(split);
+
+    let amount: i64 = amount.parse().map_err(|_| "Invalid offset amount")?;
+    if amount == 0 {
+        return Err("The offset has to be positive");
+    }
+
+    match unit {
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        _ => return Err("Invalid offset unit, use a single m, h or d"),
+    }
+    .ok_or("Offset too large")
+}
+
+/// Parses a timestamp in the ISO 8601 format `YYYY-MM-ddThh:mm`, the seconds are optional
+/// and default to `00`
+fn parse_iso(iso_raw: &str) -> Result<NaiveDateTime, &'static str> {
+    NaiveDateTime::parse_from_str(iso_raw, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(iso_raw, "%Y-%m-%dT%H:%M"))
+        .map_err(|_| "Invalid ISO 8601 date and time, expected e.g. 2024-06-02T18:30")
+}
+
+/// Parses a date in the format `YYYY-MM-dd`
+fn parse_date(date_raw: &str) -> Result<NaiveDate, &'static str> {
+    let date_raw = date_raw.trim();
+    if date_raw.split('-').count() != 3 || date_raw.is_empty() {
+        return Err("Invalid date");
+    }
+
+    // a well formed date that doesn't exist (e.g. month 13 or Feb 29 of a non-leap
+    // year) is rejected here, before any request
+    NaiveDate::parse_from_str(date_raw, "%Y-%m-%d").map_err(|e| match e.kind() {
+        ParseErrorKind::OutOfRange | ParseErrorKind::Impossible => "Invalid date",
+        _ => "Invalid date format",
+    })
+}
+
+/// Parses a time in the format `hh:mm` or `hh:mm:ss`, either optionally followed by `am`/`pm`
+fn parse_time(time_raw: &str) -> Result<NaiveTime, &'static str> {
+    let time_raw = time_raw.trim().to_lowercase();
+    let with_seconds = match time_raw.split(':').count() {
+        2 => false,
+        3 => true,
+        _ => return Err("Invalid time"),
+    };
+
+    let meridiem = time_raw.ends_with("am") || time_raw.ends_with("pm");
+    let format = match (meridiem, with_seconds) {
+        (true, true) => "%I:%M:%S%p",
+        (true, false) => "%I:%M%p",
+        (false, true) => "%H:%M:%S",
+        (false, false) => "%H:%M",
+    };
+    NaiveTime::parse_from_str(&time_raw, format).map_err(|_| "Invalid time format")
+}
+
+/// Whether the text is `am` or `pm`, case insensitive
+fn is_meridiem(text: &str) -> bool {
+    text.eq_ignore_ascii_case("am") || text.eq_ignore_ascii_case("pm")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saturday 2030-06-01 12:00, in UTC so that the wall clock is the stored time
+    fn now() -> NaiveDateTime {
+        datetime(2030, 6, 1, 12, 0, 0)
+    }
+
+    fn datetime(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, min, sec)
+            .unwrap()
+    }
+
+    fn parse(s: &str) -> Result<NaiveDateTime, &'static str> {
+        Due::parse_at(s, Zone::Named(chrono_tz::UTC), now()).map(|due| due.0)
+    }
+
+    fn due(s: &str) -> Due {
+        Due(parse(s).unwrap())
+    }
+
+    #[test]
+    fn today_and_tomorrow_resolve_against_now() {
+        assert_eq!(parse("today 18:30"), Ok(datetime(2030, 6, 1, 18, 30, 0)));
+        assert_eq!(parse("tomorrow 09:00"), Ok(datetime(2030, 6, 2, 9, 0, 0)));
+        assert_eq!(parse("TOMORROW 09:00"), Ok(datetime(2030, 6, 2, 9, 0, 0)));
+    }
+
+    #[test]
+    fn today_with_a_time_already_past_is_refused() {
+        assert!(parse("today 11:59").is_err());
+        // tomorrow is never past
+        assert_eq!(parse("tomorrow 11:59"), Ok(datetime(2030, 6, 2, 11, 59, 0)));
+    }
+
+    #[test]
+    fn a_time_rolled_to_tomorrow_is_described_as_tomorrow() {
+        let utc = Zone::Named(chrono_tz::UTC);
+
+        assert_eq!(
+            due("11:00").describe_at(utc, now()),
+            "2030-06-02 11:00 UTC (tomorrow)"
+        );
+        assert_eq!(
+            due("13:00").describe_at(utc, now()),
+            "2030-06-01 13:00 UTC (today)"
+        );
+        assert_eq!(
+            due("2030-06-05 09:00").describe_at(utc, now()),
+            "2030-06-05 09:00 UTC (Wednesday)"
+        );
+        assert_eq!(
+            due("2030-07-01 09:00").describe_at(utc, now()),
+            "2030-07-01 09:00 UTC"
+        );
+    }
+
+    #[test]
+    fn the_day_of_the_description_is_the_one_of_the_zone() {
+        // 23:30 UTC is already the next day in Rome
+        let due = Due(datetime(2030, 6, 1, 23, 30, 0));
+
+        assert_eq!(
+            due.describe_at(Zone::Named(chrono_tz::Europe::Rome), now()),
+            "2030-06-02 01:30 Europe/Rome (tomorrow)"
+        );
+    }
+
+    #[test]
+    fn a_time_already_past_rolls_to_tomorrow() {
+        assert_eq!(parse("11:00"), Ok(datetime(2030, 6, 2, 11, 0, 0)));
+        assert_eq!(parse("13:00"), Ok(datetime(2030, 6, 1, 13, 0, 0)));
+    }
+
+    #[test]
+    fn the_keywords_are_typed_in_the_zone() {
+        // 12:00 UTC is 21:00 in Tokyo, stored back in UTC
+        let tokyo = Zone::Named(chrono_tz::Asia::Tokyo);
+        let due = Due::parse_at("tomorrow 09:00", tokyo, now()).unwrap();
+
+        assert_eq!(due.0, datetime(2030, 6, 2, 0, 0, 0));
+    }
+
+    #[test]
+    fn a_weekday_resolves_to_its_next_occurrence() {
+        assert_eq!(parse("monday 09:00"), Ok(datetime(2030, 6, 3, 9, 0, 0)));
+        assert_eq!(parse("Fri 14:00"), Ok(datetime(2030, 6, 7, 14, 0, 0)));
+    }
+
+    #[test]
+    fn today_counts_as_its_weekday_only_while_the_time_is_to_come() {
+        // now is a saturday noon
+        assert_eq!(parse("saturday 18:00"), Ok(datetime(2030, 6, 1, 18, 0, 0)));
+        assert_eq!(parse("sat 09:00"), Ok(datetime(2030, 6, 8, 9, 0, 0)));
+    }
+
+    #[test]
+    fn an_offset_is_added_to_now() {
+        assert_eq!(parse("+30m"), Ok(datetime(2030, 6, 1, 12, 30, 0)));
+        assert_eq!(parse("+2h"), Ok(datetime(2030, 6, 1, 14, 0, 0)));
+        assert_eq!(parse("+1d"), Ok(datetime(2030, 6, 2, 12, 0, 0)));
+    }
+
+    #[test]
+    fn an_offset_drops_the_seconds_of_now() {
+        let now = datetime(2030, 6, 1, 12, 0, 42);
+        let due = Due::parse_at("+1h", Zone::Named(chrono_tz::UTC), now).unwrap();
+
+        assert_eq!(due.0, datetime(2030, 6, 1, 13, 0, 0));
+    }
+
+    #[test]
+    fn a_malformed_offset_is_refused() {
+        for offset in ["+5x", "+h", "+0m", "+-1h", "+1d2h", "+", "+2h 10:00"] {
+            assert!(parse(offset).is_err(), "{offset} was accepted");
+        }
+    }
+
+    #[test]
+    fn an_offset_past_the_last_date_is_refused() {
+        assert_eq!(parse("+1000000000d"), Err("Offset too large"));
+        // too large to even be a duration
+        assert_eq!(parse("+9223372036854775807m"), Err("Offset too large"));
+    }
+
+    #[test]
+    fn an_impossible_date_is_refused() {
+        assert_eq!(parse("2031-02-29 10:00"), Err("Invalid date"));
+        assert_eq!(parse("2031-13-01 10:00"), Err("Invalid date"));
+        assert_eq!(parse("2031-01-40 10:00"), Err("Invalid date"));
+    }
+
+    #[test]
+    fn feb_29_of_a_leap_year_is_accepted() {
+        assert_eq!(
+            parse("2032-02-29 10:00"),
+            Ok(datetime(2032, 2, 29, 10, 0, 0))
+        );
+    }
+
+    #[test]
+    fn midnight_and_noon_in_the_12_hour_format() {
+        assert_eq!(
+            parse("2030-06-05 12:00am"),
+            Ok(datetime(2030, 6, 5, 0, 0, 0))
+        );
+        assert_eq!(
+            parse("2030-06-05 12:00pm"),
+            Ok(datetime(2030, 6, 5, 12, 0, 0))
+        );
+    }
+
+    #[test]
+    fn the_meridiem_can_be_spaced_and_uppercase() {
+        assert_eq!(parse("2:30pm"), Ok(datetime(2030, 6, 1, 14, 30, 0)));
+        assert_eq!(parse("2:30 PM"), Ok(datetime(2030, 6, 1, 14, 30, 0)));
+        assert_eq!(
+            parse("tomorrow 9:15 am"),
+            Ok(datetime(2030, 6, 2, 9, 15, 0))
+        );
+        assert!(parse("13:00pm").is_err());
+    }
+
+    #[test]
+    fn a_past_explicit_date_is_accepted_by_default() {
+        // the warning is only logged, refusing it is up to `--no-past-due`
+        assert_eq!(parse("2020-01-01 09:00"), Ok(datetime(2020, 1, 1, 9, 0, 0)));
+    }
+
+    #[test]
+    fn a_past_explicit_date_is_past_for_no_past_due() {
+        assert!(due("2020-01-01 09:00").is_past_at(now()));
+        assert!(!due("2031-01-01 09:00").is_past_at(now()));
+        // a bare time rolls over instead of being past
+        assert!(!due("09:00").is_past_at(now()));
+    }
+
+    #[test]
+    fn an_iso_timestamp_is_taken_as_is() {
+        assert_eq!(
+            parse("2030-06-05T18:30"),
+            Ok(datetime(2030, 6, 5, 18, 30, 0))
+        );
+        assert_eq!(
+            parse("2030-06-05T18:30:15"),
+            Ok(datetime(2030, 6, 5, 18, 30, 15))
+        );
+        assert_eq!(due("2030-06-05T18:30").to_string(), "2030-06-05T18:30:00");
+        assert!(parse("2030-06-05T25:00").is_err());
+    }
+
+    #[test]
+    fn the_seconds_are_kept() {
+        assert_eq!(parse("14:30:15"), Ok(datetime(2030, 6, 1, 14, 30, 15)));
+        assert_eq!(due("14:30:15").to_string(), "2030-06-01T14:30:15");
+        assert_eq!(due("14:30").to_string(), "2030-06-01T14:30:00");
+        assert_eq!(
+            parse("2030-06-05 2:30:15pm"),
+            Ok(datetime(2030, 6, 5, 14, 30, 15))
+        );
+    }
+
+    #[test]
+    fn a_malformed_time_is_refused() {
+        for time in ["10:", "10:61", "10:30:61", "10:30:15:00", "1030"] {
+            assert!(parse(time).is_err(), "{time} was accepted");
+        }
+    }
+
+    #[test]
+    fn a_task_line_has_optional_due_and_group() {
+        let task: TaskLine = "buy milk | | home".parse().unwrap();
+
+        assert_eq!(task.description, "buy milk");
+        assert!(task.due.is_none());
+        assert_eq!(task.group.as_deref(), Some("home"));
+    }
+
+    #[test]
+    fn a_malformed_task_line_is_refused() {
+        assert!(" | 10:00".parse::<TaskLine>().is_err());
+        assert!("a | 10:00 | home | extra".parse::<TaskLine>().is_err());
+        assert!("a | 2031-13-01 10:00".parse::<TaskLine>().is_err());
+    }
+}
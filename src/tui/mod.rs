@@ -0,0 +1,380 @@
+/// # Tui Module
+///
+/// This module provides the terminal interface (`rsm tui`), the tables are listed in a sidebar
+/// and the tasks of the selected one in the main pane.
+///
+/// The interface only draws what the `Api` returns and goes through its methods for every
+/// change, so it behaves like the plain cli: the added and removed tasks are recorded for
+/// `undo` as well.
+///
+/// ## Keys
+///
+/// - `up`/`down` (`k`/`j`): Select the previous or next table or task.
+/// - `left`/`right` (`h`/`l`, `tab`): Move between the tables and the tasks.
+/// - `a`: Add a task to the selected table, typed as `<name> | <due> | <group>`.
+/// - `d`: Remove the selected task, after a confirmation.
+/// - `r`: Fetch the tables and the tasks again.
+/// - `q` (`esc`): Quit.
+///
+/// ## Functions
+///
+/// - `run`: Runs the interface until the user quits.
+use std::collections::HashMap;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, List, ListState, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::api::api_list::{GetTaskResponseDetail, TaskListing};
+use crate::api::{Api, ErrorResponse, SuccessfulResponse};
+use crate::error::{Error, Result};
+use crate::parsers::TaskLine;
+use crate::utils::config_helper::Config;
+use crate::utils::normalize_description;
+use crate::utils::op_log::{Op, OpLog};
+use crate::utils::table_formatter::FormattedResponse;
+use crate::utils::timezone::zone;
+
+const HELP: &str = "↑↓ select  ←→ switch pane  a add  d remove  r refresh  q quit";
+
+#[derive(PartialEq)]
+enum Focus {
+    Tables,
+    Tasks,
+}
+
+enum Mode {
+    Normal,
+    /// A task is being typed
+    Adding(String),
+    /// The removal of the selected task waits for a confirmation
+    ConfirmRemove,
+}
+
+struct App<'a> {
+    api: &'a Api,
+    config: &'a Config,
+    tables: Vec<String>,
+    tasks: Vec<GetTaskResponseDetail>,
+    table_state: ListState,
+    task_state: TableState,
+    focus: Focus,
+    mode: Mode,
+    /// The outcome of the last action, shown in place of the help
+    status: Option<String>,
+}
+
+/// Runs the interface until the user quits, the terminal is restored whatever happens
+///
+/// # Args
+/// - api: struct `Api` that represents the interface to the api
+/// - config: struct `Config` that represents the config management
+pub fn run(api: &Api, config: &Config) -> Result<()> {
+    let mut app = App::new(api, config)?;
+
+    let mut terminal = ratatui::init();
+    let res = app.run(&mut terminal);
+    ratatui::restore();
+    res
+}
+
+impl<'a> App<'a> {
+    fn new(api: &'a Api, config: &'a Config) -> Result<App<'a>> {
+        let mut app = App {
+            api,
+            config,
+            tables: vec![],
+            tasks: vec![],
+            table_state: ListState::default(),
+            task_state: TableState::default(),
+            focus: Focus::Tables,
+            mode: Mode::Normal,
+            status: None,
+        };
+        app.load_tables()?;
+        Ok(app)
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|_| Error::RsmFailed)?;
+
+            let Event::Key(key) = event::read().map_err(|_| Error::RsmFailed)? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match &mut self.mode {
+                Mode::Normal => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+                    KeyCode::Down | KeyCode::Char('j') => self.select(1),
+                    KeyCode::Left | KeyCode::Char('h') => self.focus = Focus::Tables,
+                    KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => {
+                        self.focus = Focus::Tasks
+                    }
+                    KeyCode::Tab => {
+                        self.focus = match self.focus {
+                            Focus::Tables => Focus::Tasks,
+                            Focus::Tasks => Focus::Tables,
+                        }
+                    }
+                    KeyCode::Char('a') if self.selected_table().is_some() => {
+                        self.mode = Mode::Adding(String::new())
+                    }
+                    KeyCode::Char('d') if self.selected_task().is_some() => {
+                        self.mode = Mode::ConfirmRemove
+                    }
+                    KeyCode::Char('r') => self.refresh(),
+                    _ => {}
+                },
+                Mode::Adding(input) => match key.code {
+                    KeyCode::Enter => {
+                        let input = std::mem::take(input);
+                        self.mode = Mode::Normal;
+                        self.add_task(&input);
+                    }
+                    KeyCode::Esc => self.mode = Mode::Normal,
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) => input.push(c),
+                    _ => {}
+                },
+                Mode::ConfirmRemove => {
+                    self.mode = Mode::Normal;
+                    if let KeyCode::Char('y') | KeyCode::Char('Y') = key.code {
+                        self.remove_task();
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, bottom] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [sidebar, pane] =
+            Layout::horizontal([Constraint::Length(24), Constraint::Min(0)]).areas(main);
+
+        let highlight = Style::default().add_modifier(Modifier::REVERSED);
+        let border = |focused: bool| match focused {
+            true => Style::default().fg(Color::Blue),
+            false => Style::default(),
+        };
+
+        let tables = List::new(self.tables.iter().map(String::as_str))
+            .block(
+                Block::bordered()
+                    .title(" tables ")
+                    .border_style(border(self.focus == Focus::Tables)),
+            )
+            .highlight_style(highlight);
+        frame.render_stateful_widget(tables, sidebar, &mut self.table_state);
+
+        let rows = self.tasks.iter().map(|task| {
+            let due = task.due.map_or_else(String::new, |due| {
+                zone().from_server(due).format("%Y-%m-%d %H:%M").to_string()
+            });
+            Row::new([task.description.clone(), task.group.clone(), due])
+        });
+        let tasks = Table::new(
+            rows,
+            [
+                Constraint::Min(20),
+                Constraint::Length(16),
+                Constraint::Length(16),
+            ],
+        )
+        .header(Row::new(["TASK", "GROUP", "DUE"]).style(Style::default().fg(Color::Blue)))
+        .block(
+            Block::bordered()
+                .title(format!(" {} ", self.selected_table().unwrap_or("tasks")))
+                .border_style(border(self.focus == Focus::Tasks)),
+        )
+        .row_highlight_style(highlight);
+        frame.render_stateful_widget(tasks, pane, &mut self.task_state);
+
+        let line = match &self.mode {
+            Mode::Adding(input) => format!("add (<name> | <due> | <group>): {input}█"),
+            Mode::ConfirmRemove => format!(
+                "remove '{}'? [y/N]",
+                self.selected_task()
+                    .map(|task| task.description.as_str())
+                    .unwrap_or_default()
+            ),
+            Mode::Normal => self.status.clone().unwrap_or_else(|| HELP.to_owned()),
+        };
+        frame.render_widget(Paragraph::new(line), bottom);
+    }
+
+    /// Moves the selection of the focused pane by `delta`, a new table gets its tasks loaded
+    fn select(&mut self, delta: isize) {
+        let (len, selected) = match self.focus {
+            Focus::Tables => (self.tables.len(), self.table_state.selected()),
+            Focus::Tasks => (self.tasks.len(), self.task_state.selected()),
+        };
+        if len == 0 {
+            return;
+        }
+
+        let next = selected
+            .map_or(0, |selected| selected.saturating_add_signed(delta))
+            .min(len - 1);
+        match self.focus {
+            Focus::Tables => {
+                if selected != Some(next) {
+                    self.table_state.select(Some(next));
+                    self.load_tasks();
+                }
+            }
+            Focus::Tasks => self.task_state.select(Some(next)),
+        }
+    }
+
+    fn selected_table(&self) -> Option<&str> {
+        self.table_state
+            .selected()
+            .and_then(|selected| self.tables.get(selected))
+            .map(String::as_str)
+    }
+
+    fn selected_task(&self) -> Option<&GetTaskResponseDetail> {
+        self.task_state
+            .selected()
+            .and_then(|selected| self.tasks.get(selected))
+    }
+
+    fn load_tables(&mut self) -> Result<()> {
+        self.tables = match self.api.get_tasks(None, HashMap::new())? {
+            TaskListing::Tables(tables) => tables.res.into_iter().map(|table| table.name).collect(),
+            TaskListing::ApiError(err) => return Err(err.into()),
+            TaskListing::Tasks(_) => return Err(Error::InvalidServerResponse),
+        };
+
+        let selected = self
+            .table_state
+            .selected()
+            .filter(|selected| *selected < self.tables.len())
+            .or(Some(0).filter(|_| !self.tables.is_empty()));
+        self.table_state.select(selected);
+        self.load_tasks();
+        Ok(())
+    }
+
+    /// Loads the tasks of the selected table, a failure is shown in the status line
+    fn load_tasks(&mut self) {
+        self.tasks.clear();
+        let Some(table) = self.selected_table().map(str::to_owned) else {
+            self.task_state.select(None);
+            return;
+        };
+
+        match self.api.get_tasks(Some(&table), HashMap::new()) {
+            Ok(TaskListing::Tasks(tasks)) => self.tasks = tasks.res,
+            Ok(TaskListing::ApiError(err)) => self.status = Some(failure(&err)),
+            Ok(TaskListing::Tables(_)) => {
+                self.status = Some(Error::InvalidServerResponse.to_string())
+            }
+            Err(e) => self.status = Some(e.to_string()),
+        }
+
+        let selected = self
+            .task_state
+            .selected()
+            .map(|selected| selected.min(self.tasks.len().saturating_sub(1)))
+            .filter(|_| !self.tasks.is_empty());
+        self.task_state.select(selected);
+    }
+
+    fn refresh(&mut self) {
+        // cleared before loading, so that a failure of the tasks reported by `load_tasks`
+        // stays shown
+        self.status = None;
+        if let Err(e) = self.load_tables() {
+            self.status = Some(e.to_string());
+        }
+    }
+
+    /// Adds the task typed as `<name> | <due> | <group>` to the selected table
+    fn add_task(&mut self, input: &str) {
+        let Some(table) = self.selected_table().map(str::to_owned) else {
+            return;
+        };
+        let task = match input.parse::<TaskLine>() {
+            Ok(task) => task,
+            Err(e) => {
+                self.status = Some(e);
+                return;
+            }
+        };
+
+        let due = task.due.as_ref().map(|due| due.to_string());
+        let mut body: HashMap<&str, &str> = HashMap::new();
+        if let Some(due) = &due {
+            body.insert("due", due);
+        }
+        if let Some(group) = &task.group {
+            body.insert("group", group);
+        }
+        body.insert("description", &task.description);
+
+        let op = normalize_description(&task.description, self.config.normalize_whitespace)
+            .ok()
+            .map(|description| Op::Add {
+                table: table.clone(),
+                description,
+            });
+        let res = self.api.add_task(table, body);
+        self.finish(res, op, format!("added '{}'", task.description));
+    }
+
+    /// Removes the selected task, it was already confirmed
+    fn remove_task(&mut self) {
+        let (Some(table), Some(task)) = (
+            self.selected_table().map(str::to_owned),
+            self.selected_task().cloned(),
+        ) else {
+            return;
+        };
+
+        let res = self
+            .api
+            .remove_task(table.clone(), task.description.clone());
+        let done = format!("removed '{}'", task.description);
+        let op = Op::Remove {
+            table,
+            tasks: vec![task],
+        };
+        self.finish(res, Some(op), done);
+    }
+
+    /// Shows the outcome of a change, records it for `undo` and fetches the tasks again
+    fn finish(&mut self, res: Result<Box<dyn FormattedResponse>>, op: Option<Op>, done: String) {
+        self.status = Some(match res {
+            Ok(res) if res.as_any().is::<SuccessfulResponse>() => {
+                if let Some(op) = op {
+                    OpLog::load(self.config).push(op);
+                }
+                done
+            }
+            Ok(res) => match res.as_any().downcast_ref::<ErrorResponse>() {
+                Some(err) => failure(err),
+                None => Error::InvalidServerResponse.to_string(),
+            },
+            Err(e) => e.to_string(),
+        });
+        self.load_tasks();
+    }
+}
+
+/// The line shown for an error sent by the server
+fn failure(err: &ErrorResponse) -> String {
+    format!("{} ({})", err.error.error_type.hint(), err.error.req_uuid)
+}
@@ -0,0 +1,112 @@
+/// # Batch Report Module
+///
+/// This module collects the outcome of every item of a bulk operation so that a
+/// single summary can be shown at the end, e.g. `12 succeeded, 2 failed ('a', 'b')`.
+///
+/// ## Types
+///
+/// - `BatchReport`: The outcomes of a bulk operation.
+/// - `BatchItem`: The outcome of a single item.
+use serde::Serialize;
+
+use crate::api::ErrorResponse;
+use crate::error::{Error, Result};
+use crate::utils::output::{emit, OutputMode};
+use crate::utils::table_formatter::FormattedResponse;
+
+#[derive(Debug, Serialize)]
+pub struct BatchItem {
+    pub item: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(transparent)]
+pub struct BatchReport {
+    items: Vec<BatchItem>,
+}
+
+impl BatchReport {
+    pub fn success(&mut self, item: impl Into<String>) {
+        self.items.push(BatchItem {
+            item: item.into(),
+            ok: true,
+            error: None,
+        });
+    }
+
+    pub fn failure(&mut self, item: impl Into<String>, error: impl Into<String>) {
+        self.items.push(BatchItem {
+            item: item.into(),
+            ok: false,
+            error: Some(error.into()),
+        });
+    }
+
+    /// Records the outcome of a request made for `item`, a response from the server
+    /// is a success unless it is an `ErrorResponse`
+    pub fn record(&mut self, item: impl Into<String>, res: Result<Box<dyn FormattedResponse>>) {
+        match res {
+            Ok(res) => match res.as_any().downcast_ref::<ErrorResponse>() {
+                Some(err) => self.failure(item, err.error.error_type.to_string().trim_end()),
+                None => self.success(item),
+            },
+            // nothing was sent, so nothing succeeded or failed
+            Err(Error::DryRun) => {}
+            Err(err) => self.failure(item, err.to_string()),
+        }
+    }
+
+    /// The outcomes, in the order they were recorded
+    pub fn items(&self) -> &[BatchItem] {
+        &self.items
+    }
+
+    pub fn succeeded(&self) -> usize {
+        self.items.iter().filter(|item| item.ok).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.items.iter().filter(|item| !item.ok).count()
+    }
+
+    /// Prints the summary, as a json array of the outcomes in json mode
+    pub fn print(&self, output: OutputMode) {
+        match output {
+            OutputMode::Human => emit(&self.to_string()),
+            OutputMode::Json => emit(&format!("{}\n", serde_json::json!(self))),
+        }
+    }
+}
+
+impl std::fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for item in self.items.iter().filter(|item| !item.ok) {
+            writeln!(
+                f,
+                "\x1b[31mfailed\x1b[0m '{}': {}",
+                item.item,
+                item.error.as_deref().unwrap_or_default()
+            )?;
+        }
+
+        write!(
+            f,
+            "{} succeeded, {} failed",
+            self.succeeded(),
+            self.failed()
+        )?;
+        if self.failed() > 0 {
+            let failed: Vec<String> = self
+                .items
+                .iter()
+                .filter(|item| !item.ok)
+                .map(|item| format!("'{}'", item.item))
+                .collect();
+            write!(f, " ({})", failed.join(", "))?;
+        }
+        writeln!(f)
+    }
+}
@@ -0,0 +1,130 @@
+/// # Cli Helpers
+///
+/// The helpers only the command line needs, the prompts, the spinner, the input files and the
+/// log file. They are only built with the `cli` feature and re-exported by `utils`.
+use std::{
+    cmp::min,
+    env, fs,
+    io::{self, IsTerminal},
+    path::PathBuf,
+};
+
+use crate::error::{Error, Result};
+use crate::parsers::LineRange;
+
+use super::{config_helper, output};
+
+pub enum Choice {
+    Yes,
+    No,
+}
+
+impl Choice {
+    pub fn from_input(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "yes" | "y" => Some(Self::Yes),
+            "no" | "n" => Some(Self::No),
+            _ => None,
+        }
+    }
+}
+
+/// prompts the user asking if he has already a key, and retrives his choice,
+/// a closed stdin is an error instead of an endless prompt
+pub fn get_user_choice() -> io::Result<Choice> {
+    loop {
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        if let Some(choice) = Choice::from_input(&input) {
+            break Ok(choice);
+        } else {
+            println!("Invalid input. Please enter 'yes' ('y') or 'no' ('n').");
+        }
+    }
+}
+
+/// Asks a yes/no question on stderr before a destructive operation, anything but a yes
+/// (an empty answer included) is a no. Without a terminal on stdin nobody can answer,
+/// so pipelines and scripts are never blocked and the answer is yes
+pub fn confirm(question: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Ok(true);
+    }
+
+    eprint!("{question} [y/N]: ");
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .map_err(|_| Error::RsmFailed)?;
+    Ok(matches!(Choice::from_input(&input), Some(Choice::Yes)))
+}
+
+pub fn resolve_file_input(
+    file: &PathBuf,
+    line: Option<&u16>,
+    range: Option<&LineRange>,
+) -> io::Result<String> {
+    if !file.exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "File not found"));
+    }
+    let content = fs::read_to_string(file)?;
+    if content.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "File is empty"));
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let result = match (line, range) {
+        (Some(line_num), _) => lines
+            .get(*line_num as usize - 1)
+            .map_or("", |&line| line)
+            .trim()
+            .to_owned(),
+        (_, Some(range)) => {
+            let range = range.clone();
+            let end = *range.0.end() as usize;
+
+            let start_index = *range.0.start() as usize - 1;
+            let end_index = min(end, lines.len());
+
+            lines[start_index..end_index].join(" ").trim().to_owned()
+        }
+        _ => content.trim().to_owned(),
+    };
+
+    if result.len() > 256 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Data too long"));
+    }
+
+    Ok(result)
+}
+
+/// Returns the path of the log file, `LOG` wins over the config directory of the platform
+pub fn find_log_path() -> String {
+    if let Ok(path) = env::var("LOG") {
+        return path;
+    }
+
+    config_helper::project_dir()
+        .map(|dir| dir.join("rsm.log").to_string_lossy().into_owned())
+        .unwrap_or_else(|| "rsm.log".to_owned())
+}
+
+/// Runs `f` showing a spinner until it returns, the spinner is only shown when
+/// stdout is a terminal and it isn't turned off (see `output::set_spinner`) so it never
+/// ends up in piped or machine output
+pub fn with_spinner<T>(f: impl FnOnce() -> T) -> T {
+    if !output::spinner_enabled() || !io::stdout().is_terminal() {
+        return f();
+    }
+
+    let handle = terminal_spinners::SpinnerBuilder::new()
+        .spinner(&terminal_spinners::DOTS)
+        .text("Contacting server...")
+        .start();
+    let res = f();
+    handle.stop_and_clear();
+    res
+}
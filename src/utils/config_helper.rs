@@ -5,27 +5,45 @@
 /// ## Functions
 ///
 /// - `get_config`: Reads the configuration file and returns a `Config` struct.
+/// - `from_env`: Builds a `Config` from the `CLI_CLIENT_TOKEN` (or `RSM_TOKEN`) environment
+///   variable, used for CI.
 /// - `update_config`: Updates the configuration file with new values.
 /// - `load_token`: Loads the token from the configuration file.
+/// - `project_dir`: Returns the config directory of the platform, used when neither
+///   `CLI_CLIENT_CONFIG` nor `CONFIG` is set.
+/// - `state_file_path`: Returns the path of a state file kept next to the config file.
+/// - `check_config_file`: Checks the config file as it is on disk, without loading (and
+///   possibly resetting) it.
+/// - `check_config`: Checks the contents of a config file, see `ConfigCheck`.
+///
+/// ## Profiles
+///
+/// The config holds one `Profile` per account, the `key`, `token`, `username` and `backend` of
+/// `Config` are the ones of the `active_profile`: they are filled from it when the config is read
+/// and stored back into it when the config is written. A config written before the profiles
+/// existed has those fields at the top level, they become the `default` profile on the first load.
 ///
 /// ## Types
 ///
-/// - `Token`: Represents an API token.
+/// - `Token`: Represents an API token, the session cookie with its `Expires` attribute.
+/// - `Profile`: Represents an account, with its own key, token and backend.
+/// - `Frontend`: The interface opened when `rsm` is ran without a subcommand.
 /// - `Config`: Represents the application configuration.
+/// - `ConfigCheck`: What `check_config` found in a config and the problems it has.
 ///
 /// ## Examples
 ///
-/// ```rust
-/// use custom_utils::{Config, Token};
+/// ```rust,no_run
+/// use rsm::utils::config_helper::Config;
 ///
 /// // Get the configuration
 /// let config = Config::get_config().expect("Failed to get config");
 ///
 /// // Update the configuration
 /// let updated_config = Config {
-///     key: Some("new_key".to_string()),
-///     first_run: false,
-///     token: Some("new_token".to_string()),
+///     default_table: Some("todo".to_string()),
+///     normalize_whitespace: true,
+///     ..config
 /// };
 /// updated_config.update_config().expect("Failed to update config");
 ///
@@ -33,18 +51,74 @@
 /// let token = Config::load_token().expect("Failed to load token");
 /// ```
 use std::{
+    collections::BTreeMap,
     env,
-    fs::File,
+    fs::{File, OpenOptions},
     io::{Read, Write},
+    path::PathBuf,
 };
 
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+use chrono::{DateTime, Utc};
+use directories::ProjectDirs;
+
+use crate::api::{DEFAULT_RETRIES, DEFAULT_TIMEOUT_SECS};
 use crate::error::{Error, Result};
+use crate::utils::timezone::Zone;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 
-// search for the path where to put the config
+const CONFIG_FILE_NAME: &str = "rsm-conf.json";
+/// Environment variable with the path of the config file, used verbatim
+pub const CONFIG_PATH_ENV: &str = "CLI_CLIENT_CONFIG";
+
+// search for the path where to put the config, `CLI_CLIENT_CONFIG` wins over `CONFIG` which
+// wins over the config directory of the platform (e.g. `~/.config/cli_client`), an empty path
+// means none was found
 fn find_config() -> String {
-    env::var("CONFIG").unwrap()
+    if let Ok(path) = env::var(CONFIG_PATH_ENV) {
+        return path;
+    }
+    if let Ok(path) = env::var("CONFIG") {
+        return path;
+    }
+
+    project_dir()
+        .map(|dir| dir.join(CONFIG_FILE_NAME).to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Returns the config directory of the platform for the app, creating it if missing
+pub fn project_dir() -> Option<PathBuf> {
+    let dir = ProjectDirs::from("", "", "cli_client")?
+        .config_dir()
+        .to_path_buf();
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        log::error!("Error in creating the config directory {e}");
+        return None;
+    }
+    Some(dir)
+}
+
+/// Returns the path of a state file kept next to the config file
+pub fn state_file_path(file_name: &str) -> PathBuf {
+    PathBuf::from(&*CONFIG_FILE).with_file_name(file_name)
+}
+
+/// Environment variable providing the token without a config file
+pub const TOKEN_ENV: &str = "RSM_TOKEN";
+/// Environment variable providing the token, named like `CLI_CLIENT_CONFIG`, it wins over
+/// `TOKEN_ENV`
+pub const CLIENT_TOKEN_ENV: &str = "CLI_CLIENT_TOKEN";
+
+/// Returns the token given by the environment, `CLI_CLIENT_TOKEN` first then `RSM_TOKEN`,
+/// an empty variable counts as unset
+fn env_token() -> Option<String> {
+    [CLIENT_TOKEN_ENV, TOKEN_ENV]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|token| !token.trim().is_empty())
 }
 
 lazy_static::lazy_static! {
@@ -53,12 +127,39 @@ lazy_static::lazy_static! {
     };
 }
 
+/// The format of the `Expires` attribute of the session cookie
+pub const COOKIE_EXPIRES_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
 #[derive(Deserialize, Clone, Default)]
 pub struct Token(String);
 
-impl Into<String> for Token {
-    fn into(self) -> String {
+impl Token {
+    /// Returns when the session cookie expires, read from the `Expires` attribute stored
+    /// at login. `None` when the token has no (valid) expiry, e.g. one given by the environment
+    pub fn expires(&self) -> Option<DateTime<Utc>> {
+        self.expires_attribute()
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+            .map(|expires| expires.with_timezone(&Utc))
+    }
+
+    /// Returns the raw `Expires` attribute of the session cookie, whether it parses or not
+    fn expires_attribute(&self) -> Option<&str> {
         self.0
+            .split(';')
+            .filter_map(|attribute| attribute.trim().split_once('='))
+            .find(|(name, _)| name.eq_ignore_ascii_case("expires"))
+            .map(|(_, value)| value.trim())
+    }
+
+    /// Whether the session cookie is past its expiry, a token without one never expires
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires().is_some_and(|expires| expires <= now)
+    }
+}
+
+impl From<Token> for String {
+    fn from(value: Token) -> String {
+        value.0
     }
 }
 
@@ -68,61 +169,380 @@ impl From<String> for Token {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// The name of the profile used when none was chosen, and of the one an old config becomes
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// An account, the config keeps one per name
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Profile {
+    pub key: Option<String>,
+    pub token: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// The url of the server of the account, the built-in one when unset
+    #[serde(default)]
+    pub backend: Option<String>,
+}
+
+/// The interface opened when `rsm` is ran without a subcommand, the cli shows the help
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frontend {
+    #[default]
+    Cli,
+    Tui,
+}
+
+impl std::str::FromStr for Frontend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "cli" => Ok(Frontend::Cli),
+            "tui" => Ok(Frontend::Tui),
+            _ => Err(Error::InvalidConfigValue),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
+    /// The key of the active profile
+    #[serde(skip_serializing)]
     pub key: Option<String>,
+    /// The token of the active profile
+    #[serde(skip_serializing)]
     pub token: Option<String>,
+    /// The username of the account, known once the user signs up or resets the key,
+    /// it is not a secret
+    #[serde(default, skip_serializing)]
+    pub username: Option<String>,
+    /// Whether the user still has to log in or sign up, a config without it is a new one
+    #[serde(default = "default_true")]
     pub first_run: bool,
+    /// The table used when a command is ran without a tablename
+    #[serde(default)]
+    pub default_table: Option<String>,
+    /// Whether the inner whitespace of task descriptions gets collapsed before sending
+    #[serde(default = "default_true")]
+    pub normalize_whitespace: bool,
+    /// The IANA timezone dues are typed and shown in, the system one when unset
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// The url of the server of the active profile, the built-in one when unset
+    #[serde(default, skip_serializing)]
+    pub backend: Option<String>,
+    /// Skips the verification of the TLS certificates, only for self-signed dev servers
+    #[serde(default)]
+    pub insecure: bool,
+    /// How many times a rate limited request, or a read the server failed to answer, is retried
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// How long a request may take before giving up, in seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// The interface opened without a subcommand
+    #[serde(default)]
+    pub frontend: Frontend,
+    /// The accounts by name
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// The name of the profile in use
+    #[serde(default = "default_profile")]
+    pub active_profile: String,
+    /// Set when the config comes from the environment, it is never written to disk
+    #[serde(skip)]
+    pub ephemeral: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_profile() -> String {
+    DEFAULT_PROFILE.to_owned()
+}
+
+fn default_retries() -> u32 {
+    DEFAULT_RETRIES
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            key: None,
+            token: None,
+            username: None,
+            first_run: true,
+            default_table: None,
+            normalize_whitespace: true,
+            timezone: None,
+            backend: None,
+            insecure: false,
+            retries: DEFAULT_RETRIES,
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            frontend: Frontend::Cli,
+            profiles: BTreeMap::new(),
+            active_profile: default_profile(),
+            ephemeral: false,
+        }
+    }
 }
 
 impl Config {
     pub fn get_config() -> Result<Config> {
+        if let Some(config) = Config::from_env() {
+            return Ok(config);
+        }
+
         read_file().map_err(|e| {
             log::error!("Error in reading the file {e}");
             Error::InvalidConfig
         })
     }
 
+    /// Builds a config from `CLI_CLIENT_TOKEN` (or `RSM_TOKEN`) if it is set, this config
+    /// never touches the disk so the app can run in read-only environments, without a
+    /// config file
+    pub fn from_env() -> Option<Config> {
+        let token = env_token()?;
+        Some(Config {
+            token: Some(token),
+            first_run: false,
+            ephemeral: true,
+            ..Config::default()
+        })
+    }
+
     pub fn update_config(&self) -> Result<()> {
-        write_config(
-            &*CONFIG_FILE,
-            self.key.as_deref(),
-            self.first_run,
-            self.token.as_deref(),
-        )
-        .map_err(|e| {
+        if self.ephemeral {
+            return Ok(());
+        }
+
+        let mut stored = self.clone();
+        stored
+            .profiles
+            .insert(self.active_profile.clone(), self.profile());
+        write_config(&CONFIG_FILE, &stored).map_err(|e| {
             log::error!("Error in updating file {e}");
             Error::FailedToUpdateConf
         })
     }
 
+    /// Returns the account fields of the config as a profile
+    pub fn profile(&self) -> Profile {
+        Profile {
+            key: self.key.clone(),
+            token: self.token.clone(),
+            username: self.username.clone(),
+            backend: self.backend.clone(),
+        }
+    }
+
+    /// Makes `name` the active profile, the account fields of the config become its ones
+    /// and the current ones are kept in the profile they belong to
+    pub fn use_profile(&mut self, name: &str) -> Result<()> {
+        let profile = self
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or(Error::ProfileNotFound)?;
+        self.profiles
+            .insert(self.active_profile.clone(), self.profile());
+
+        self.active_profile = name.to_owned();
+        self.set_profile(profile);
+        // a profile that never logged in goes through the first run
+        self.first_run = self.token.is_none();
+        Ok(())
+    }
+
+    /// Fills the account fields from the active profile, a config without profiles was
+    /// written before they existed so its account becomes the active (`default`) profile
+    fn load_profile(&mut self) {
+        match self.profiles.get(&self.active_profile).cloned() {
+            Some(profile) => self.set_profile(profile),
+            None => {
+                log::info!(
+                    "Moving the account into the '{}' profile",
+                    self.active_profile
+                );
+                self.profiles
+                    .insert(self.active_profile.clone(), self.profile());
+            }
+        }
+    }
+
+    fn set_profile(&mut self, profile: Profile) {
+        self.key = profile.key;
+        self.token = profile.token;
+        self.username = profile.username;
+        self.backend = profile.backend;
+    }
+
     pub fn load_token() -> Result<Token> {
+        if let Some(token) = env_token() {
+            return Ok(Token::from(token));
+        }
+
         let mut file = File::open(&*CONFIG_FILE).map_err(|_| Error::InvalidConfig)?;
 
         let mut contents = String::new();
         file.read_to_string(&mut contents)
             .map_err(|_| Error::FailedToReadConfig)?;
 
-        let data: Config = serde_json::from_str(&contents).map_err(|_| Error::InvalidConfig)?;
+        let mut data: Config = serde_json::from_str(&contents).map_err(|_| Error::InvalidConfig)?;
+        data.load_profile();
         let token: Token = Token::from(data.token.ok_or(Error::NoAuth)?);
         Ok(token)
     }
 }
 
+/// What `check_config` found in a config, it is valid when it has no problems
+#[derive(Debug, Default, Serialize)]
+pub struct ConfigCheck {
+    /// The path of the config file checked
+    pub path: String,
+    pub active_profile: Option<String>,
+    /// The url of the server of the active profile, `None` for the built-in one
+    pub backend: Option<String>,
+    pub logged_in: bool,
+    /// When the session of the active profile expires, `None` when it never does or the
+    /// expiry can't be read (which is a problem)
+    pub token_expires: Option<DateTime<Utc>>,
+    pub problems: Vec<String>,
+}
+
+impl ConfigCheck {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Checks the config file as it is on disk, a missing or unreadable file is a problem. The
+/// file is never written, unlike `get_config` which resets a corrupt one
+pub fn check_config_file(now: DateTime<Utc>) -> ConfigCheck {
+    let path = CONFIG_FILE.clone();
+    let mut check = match std::fs::read_to_string(&path) {
+        Ok(contents) => check_config(&contents, now),
+        Err(e) => ConfigCheck {
+            problems: vec![format!("The config file can't be read: {e}")],
+            ..ConfigCheck::default()
+        },
+    };
+    check.path = path;
+    check
+}
+
+/// Checks the contents of a config file: that it parses, that the backends of the profiles
+/// are http(s) urls, that the timezone exists and that the session of the active profile has
+/// a readable expiry that isn't past. A session without an expiry is valid, it never expires
+pub fn check_config(contents: &str, now: DateTime<Utc>) -> ConfigCheck {
+    let mut check = ConfigCheck::default();
+    let mut config: Config = match serde_json::from_str(contents) {
+        Ok(config) => config,
+        Err(e) => {
+            check
+                .problems
+                .push(format!("The config can't be parsed: {e}"));
+            return check;
+        }
+    };
+    config.load_profile();
+
+    check.active_profile = Some(config.active_profile.clone());
+    check.backend = config.backend.clone();
+    check.logged_in = config.token.is_some();
+
+    for (name, profile) in &config.profiles {
+        let Some(backend) = &profile.backend else {
+            continue;
+        };
+        if let Some(problem) = check_backend(backend) {
+            check.problems.push(format!(
+                "The backend '{backend}' of the profile '{name}' {problem}"
+            ));
+        }
+    }
+    if let Some(timezone) = &config.timezone {
+        if timezone.parse::<Zone>().is_err() {
+            check
+                .problems
+                .push(format!("The timezone '{timezone}' is not an IANA timezone"));
+        }
+    }
+    if let Some(token) = config.token.map(Token::from) {
+        match (token.expires_attribute(), token.expires()) {
+            (Some(raw), None) => check
+                .problems
+                .push(format!("The expiry of the session '{raw}' can't be read")),
+            (_, Some(expires)) if expires <= now => check.problems.push(format!(
+                "The session expired on {}, log in again",
+                expires.format("%Y-%m-%d %H:%M UTC")
+            )),
+            _ => {}
+        }
+        check.token_expires = token.expires();
+    }
+    check
+}
+
+/// Returns what is wrong with a backend url, `None` when it is a valid http(s) url
+fn check_backend(backend: &str) -> Option<String> {
+    match reqwest::Url::parse(backend) {
+        Ok(url) if !matches!(url.scheme(), "http" | "https") => {
+            Some("is not an http(s) url".to_owned())
+        }
+        Ok(url) if url.host_str().is_none() => Some("has no host".to_owned()),
+        Ok(_) => None,
+        Err(e) => Some(format!("is not a valid url: {e}")),
+    }
+}
+
 fn read_file() -> std::io::Result<Config> {
-    if !file_exists_or_empty(&*CONFIG_FILE)? {
-        write_config(&*CONFIG_FILE, None, true, None)?;
+    read_config_file(&CONFIG_FILE)
+}
+
+/// Reads the config at `file_path`, a missing or empty one is created with the defaults and a
+/// corrupt one is reset, see `recover_corrupt_config`
+fn read_config_file(file_path: &str) -> std::io::Result<Config> {
+    if !file_exists_or_empty(file_path)? {
+        write_config(file_path, &Config::default())?;
     }
 
-    let mut file = File::open(&*CONFIG_FILE)?;
+    let mut file = File::open(file_path)?;
 
     let mut contents = String::new();
     file.read_to_string(&mut contents)?;
 
-    let data: Config = serde_json::from_str(&contents)?;
+    // the missing fields get their defaults, only a file that isn't json is corrupt, a
+    // wrong value (e.g. an unknown frontend) is left for the user to fix
+    let mut data: Config = match serde_json::from_str(&contents) {
+        Ok(data) => data,
+        Err(e) if e.is_data() => return Err(e.into()),
+        Err(e) => recover_corrupt_config(file_path, &e)?,
+    };
+    data.load_profile();
     Ok(data)
 }
 
+/// Moves a config that can't be parsed to `<config>.bak` and writes a default one in its
+/// place, so the app is usable again after logging in
+fn recover_corrupt_config(file_path: &str, e: &serde_json::Error) -> std::io::Result<Config> {
+    let backup = format!("{file_path}.bak");
+    log::warn!("The config file is corrupt ({e}), moving it to {backup} and starting over");
+    std::fs::rename(file_path, &backup)?;
+    eprintln!("\x1b[33mThe config file was corrupt, it was moved to {backup} and a new one was created, log in again\x1b[0m");
+
+    let config = Config::default();
+    write_config(file_path, &config)?;
+    Ok(config)
+}
+
 fn file_exists_or_empty(file_path: &str) -> std::io::Result<bool> {
     if let Ok(metadata) = std::fs::metadata(file_path) {
         if metadata.len() == 0 {
@@ -134,21 +554,238 @@ fn file_exists_or_empty(file_path: &str) -> std::io::Result<bool> {
     }
 }
 
-fn write_config(
-    file_path: &str,
-    key: Option<&str>,
-    first_run: bool,
-    token: Option<&str>,
-) -> std::io::Result<()> {
-    let default_json = json!({
-        "key": key,
-        "first_run": first_run,
-        "token": token
-    });
+/// Writes the config to a temporary file next to it and renames it over the config, so a
+/// crash or a full disk never leaves a truncated config. The file holds the token so on
+/// unix only the user can read it
+fn write_config(file_path: &str, config: &Config) -> std::io::Result<()> {
+    let json_string = serde_json::to_string_pretty(config)?;
 
-    let json_string = serde_json::to_string_pretty(&default_json)?;
+    let tmp_path = format!("{file_path}.tmp");
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
 
-    let mut file = File::create(&file_path)?;
+    let mut file = options.open(&tmp_path)?;
     file.write_all(json_string.as_bytes())?;
-    Ok(())
+    file.sync_all()?;
+    // the mode only applies to new files, a temporary file left by a crash keeps its own
+    #[cfg(unix)]
+    std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))?;
+
+    std::fs::rename(&tmp_path, file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns an empty directory of its own for a test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rsm-config-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn path_in(dir: &std::path::Path) -> String {
+        dir.join(CONFIG_FILE_NAME).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn write_config_leaves_no_temporary_file() {
+        let dir = temp_dir("write");
+        let path = path_in(&dir);
+        let config = Config {
+            default_table: Some("work".to_owned()),
+            ..Config::default()
+        };
+
+        write_config(&path, &config).unwrap();
+
+        let written: Config =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written.default_table.as_deref(), Some("work"));
+        assert!(!PathBuf::from(format!("{path}.tmp")).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_config_is_only_readable_by_the_user() {
+        let dir = temp_dir("mode");
+        let path = path_in(&dir);
+        // a temporary file left by a crash with a wider mode
+        std::fs::write(format!("{path}.tmp"), "{").unwrap();
+        std::fs::set_permissions(
+            format!("{path}.tmp"),
+            std::fs::Permissions::from_mode(0o644),
+        )
+        .unwrap();
+
+        write_config(&path, &Config::default()).unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_write_interrupted_before_the_rename_keeps_the_config() {
+        let dir = temp_dir("partial");
+        let path = path_in(&dir);
+        let config = Config {
+            default_table: Some("work".to_owned()),
+            ..Config::default()
+        };
+        write_config(&path, &config).unwrap();
+
+        // the crash happened after writing part of the temporary file
+        std::fs::write(format!("{path}.tmp"), r#"{"default_table": "ho"#).unwrap();
+
+        let kept: Config = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(kept.default_table.as_deref(), Some("work"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_truncated_config_is_backed_up_and_reset() {
+        let dir = temp_dir("truncated");
+        let path = path_in(&dir);
+        let truncated = r#"{"default_table": "work", "profiles": {"default": {"tok"#;
+        std::fs::write(&path, truncated).unwrap();
+
+        let config = read_config_file(&path).unwrap();
+
+        assert_eq!(config.default_table, None);
+        assert!(config.first_run);
+        assert_eq!(
+            std::fs::read_to_string(format!("{path}.bak")).unwrap(),
+            truncated
+        );
+        let reset: Config = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(reset.default_table, None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_config_missing_fields_gets_the_defaults() {
+        let dir = temp_dir("missing-fields");
+        let path = path_in(&dir);
+        std::fs::write(&path, r#"{"first_run": false, "default_table": "work"}"#).unwrap();
+
+        let config = read_config_file(&path).unwrap();
+
+        assert_eq!(config.default_table.as_deref(), Some("work"));
+        assert!(!config.first_run);
+        assert_eq!(config.retries, DEFAULT_RETRIES);
+        assert_eq!(config.timeout_secs, DEFAULT_TIMEOUT_SECS);
+        assert!(!PathBuf::from(format!("{path}.bak")).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn the_frontend_round_trips_through_serde() {
+        for frontend in [Frontend::Cli, Frontend::Tui] {
+            let json = serde_json::to_string(&frontend).unwrap();
+            assert_eq!(serde_json::from_str::<Frontend>(&json).unwrap(), frontend);
+        }
+        assert_eq!(serde_json::to_string(&Frontend::Tui).unwrap(), r#""tui""#);
+        assert!(serde_json::from_str::<Frontend>(r#""gui""#).is_err());
+    }
+
+    #[test]
+    fn the_frontend_is_parsed_from_the_command_line() {
+        assert_eq!(" TUI ".parse::<Frontend>().unwrap(), Frontend::Tui);
+        assert_eq!("cli".parse::<Frontend>().unwrap(), Frontend::Cli);
+        assert!(matches!(
+            "gui".parse::<Frontend>(),
+            Err(Error::InvalidConfigValue)
+        ));
+    }
+
+    #[test]
+    fn a_config_with_a_wrong_value_is_not_reset() {
+        let dir = temp_dir("wrong-value");
+        let path = path_in(&dir);
+        std::fs::write(&path, r#"{"frontend": "gui"}"#).unwrap();
+
+        assert!(read_config_file(&path).is_err());
+        assert!(!PathBuf::from(format!("{path}.bak")).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_config_without_profiles_becomes_the_default_profile() {
+        let dir = temp_dir("migration");
+        let path = path_in(&dir);
+        // written before the profiles existed
+        std::fs::write(
+            &path,
+            r#"{"key": "the-key", "token": "id=abc", "backend": "https://tasks.example.com", "first_run": false}"#,
+        )
+        .unwrap();
+
+        let config = read_config_file(&path).unwrap();
+
+        assert_eq!(config.active_profile, DEFAULT_PROFILE);
+        assert_eq!(config.token.as_deref(), Some("id=abc"));
+        let profile = &config.profiles[DEFAULT_PROFILE];
+        assert_eq!(profile.key.as_deref(), Some("the-key"));
+        assert_eq!(profile.token.as_deref(), Some("id=abc"));
+        assert_eq!(
+            profile.backend.as_deref(),
+            Some("https://tasks.example.com")
+        );
+
+        // once written the account only lives in the profile
+        write_config(&path, &config).unwrap();
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(stored.get("token").is_none());
+        assert_eq!(stored["profiles"][DEFAULT_PROFILE]["token"], "id=abc");
+        let reloaded = read_config_file(&path).unwrap();
+        assert_eq!(reloaded.key.as_deref(), Some("the-key"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn the_active_profile_fills_the_account() {
+        let dir = temp_dir("active-profile");
+        let path = path_in(&dir);
+        std::fs::write(
+            &path,
+            r#"{"active_profile": "work", "profiles": {
+                "default": {"key": "home-key", "token": "id=home"},
+                "work": {"key": "work-key", "token": "id=work", "backend": "https://work.example.com"}
+            }}"#,
+        )
+        .unwrap();
+
+        let config = read_config_file(&path).unwrap();
+
+        assert_eq!(config.key.as_deref(), Some("work-key"));
+        assert_eq!(config.token.as_deref(), Some("id=work"));
+        assert_eq!(config.backend.as_deref(), Some("https://work.example.com"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_session_past_its_expires_is_expired() {
+        let now = DateTime::parse_from_rfc3339("2030-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let token = |cookie: &str| Token::from(cookie.to_owned());
+
+        assert!(
+            token("id=abc; Path=/; HttpOnly; Expires=Sat, 01 Jun 2030 11:59:59 GMT")
+                .is_expired(now)
+        );
+        assert!(
+            !token("id=abc; Path=/; HttpOnly; Expires=Sat, 01 Jun 2030 12:00:01 GMT")
+                .is_expired(now)
+        );
+        // a token without an expiry (e.g. from the environment) never expires
+        assert!(!token("id=abc").is_expired(now));
+    }
 }
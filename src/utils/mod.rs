@@ -4,14 +4,28 @@
 ///
 /// ## Submodules
 ///
+/// - `batch_report`: Provides the summary of the bulk operations.
 /// - `config_helper`: Provides functionality for working with configuration files.
+/// - `table_cache`: Provides the cache of the characteristics of the tables.
 /// - `table_formatter`: Provides functionality for formatting table data.
+/// - `notifications`: Provides the desktop notifications for the overdue tasks.
+/// - `op_log`: Provides the log of the last operations used by `undo`.
+/// - `output`: Provides the output mode (human or json) and the printing of responses.
+/// - `timezone`: Provides the timezone dues are typed and shown in.
+/// - `wizard`: Provides the questions of `add --interactive`.
+///
+/// `notifications`, `wizard` and the functions and types from `cli` are only built with the
+/// `cli` feature, `output` is then public too.
 ///
 /// ## Functions
 ///
 /// - `get_user_choice`: Prompts the user to enter a choice ('yes' or 'no').
+/// - `confirm`: Asks for a confirmation before a destructive operation.
 /// - `resolve_file_input`: Resolves input from a file, optionally extracting a single line or a range of lines.
 /// - `find_log_path`: Finds the path to the log file.
+/// - `with_spinner`: Shows a spinner while a request is in flight.
+/// - `normalize_description`: Trims a task description and collapses its inner whitespace.
+/// - `validate_tablename`: Trims a table name and refuses the ones that can't be created.
 ///
 /// ## Types
 ///
@@ -19,9 +33,9 @@
 ///
 /// ## Examples
 ///
-/// ```rust
+/// ```rust,no_run
 /// use std::path::PathBuf;
-/// use custom_utils::{Choice, get_user_choice, resolve_file_input, find_log_path};
+/// use rsm::utils::{find_log_path, get_user_choice, resolve_file_input};
 ///
 /// let choice = get_user_choice().expect("Failed to get user choice");
 ///
@@ -30,81 +44,51 @@
 ///
 /// let log_path = find_log_path();
 /// ```
+pub mod batch_report;
+#[cfg(feature = "cli")]
+mod cli;
 pub mod config_helper;
+#[cfg(feature = "cli")]
+pub mod notifications;
+pub mod op_log;
+#[cfg(feature = "cli")]
+pub mod output;
+// the library prints through it too, but only the cli sets it up
+#[cfg(not(feature = "cli"))]
+#[allow(dead_code)]
+pub(crate) mod output;
+pub mod table_cache;
 pub mod table_formatter;
+pub mod timezone;
+#[cfg(feature = "cli")]
+pub mod wizard;
 
-use std::{cmp::min, env, fs, io, path::PathBuf};
-
-use crate::parsers::LineRange;
+#[cfg(feature = "cli")]
+pub use cli::{confirm, find_log_path, get_user_choice, resolve_file_input, with_spinner, Choice};
 
-pub enum Choice {
-    Yes,
-    No,
-}
+use crate::error::{Error, Result};
 
-impl Choice {
-    pub fn from_input(input: &str) -> Option<Self> {
-        match input.trim().to_lowercase().as_str() {
-            "yes" | "y" => Some(Self::Yes),
-            "no" | "n" => Some(Self::No),
-            _ => None,
-        }
+/// Trims a task description and, if `collapse` is set, collapses the inner runs
+/// of whitespace into a single space, an all-whitespace description is rejected
+pub fn normalize_description(desc: &str, collapse: bool) -> Result<String> {
+    let desc = desc.trim();
+    if desc.is_empty() {
+        return Err(Error::EmptyTaskName);
     }
-}
 
-/// prompts the user asking if he has already a key, and retrives his choice
-pub fn get_user_choice() -> io::Result<Choice> {
-    loop {
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-
-        if let Some(choice) = Choice::from_input(&input) {
-            break Ok(choice);
-        } else {
-            println!("Invalid input. Please enter 'yes' ('y') or 'no' ('n').");
-        }
+    if collapse {
+        Ok(desc.split_whitespace().collect::<Vec<&str>>().join(" "))
+    } else {
+        Ok(desc.to_owned())
     }
 }
 
-pub fn resolve_file_input(
-    file: &PathBuf,
-    line: Option<&u16>,
-    range: Option<&LineRange>,
-) -> io::Result<String> {
-    if !file.exists() {
-        return Err(io::Error::new(io::ErrorKind::NotFound, "File not found"));
-    }
-    let content = fs::read_to_string(&file)?;
-    if content.is_empty() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "File is empty"));
-    }
-
-    let lines: Vec<&str> = content.lines().collect();
-    let result = match (line, range) {
-        (Some(line_num), _) => lines
-            .get(*line_num as usize - 1)
-            .map_or("", |&line| line)
-            .trim()
-            .to_owned(),
-        (_, Some(range)) => {
-            let range = range.clone();
-            let end = *range.0.end() as usize;
-
-            let start_index = *range.0.start() as usize - 1;
-            let end_index = min(end, lines.len());
-
-            lines[start_index..end_index].join(" ").trim().to_owned()
-        }
-        _ => content.trim().to_owned(),
-    };
-
-    if result.len() > 256 {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "Data too long"));
+/// Trims a table name, an empty name, one with a `/` (it would be read as a path by the
+/// server) or the name of a built-in table is rejected
+pub fn validate_tablename(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() || name.contains('/') || ["reminder", "todo"].contains(&name) {
+        return Err(Error::InvalidTableName);
     }
-
-    Ok(result)
-}
-
-pub fn find_log_path() -> String {
-    env::var("LOG").unwrap()
+    Ok(name.to_owned())
 }
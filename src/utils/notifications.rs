@@ -0,0 +1,95 @@
+/// # Notifications Module
+///
+/// This module sends the desktop notifications for the overdue tasks (`notify`).
+///
+/// It is meant to be ran periodically (cron, systemd timers), so the last time each task was
+/// notified is kept in a small state file next to the config and a task is notified again only
+/// once the given window has passed.
+///
+/// ## Types
+///
+/// - `NotifiedLog`: When each task was last notified.
+///
+/// ## Functions
+///
+/// - `send_overdue_notification`: Shows the desktop notification for an overdue task.
+use std::{collections::HashMap, fs};
+
+use chrono::{DateTime, Duration, Utc};
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+use crate::api::api_list::GetTaskResponseDetail;
+use crate::error::{Error, Result};
+use crate::utils::config_helper::{state_file_path, Config};
+use crate::utils::timezone::zone;
+
+const NOTIFIED_FILE: &str = "notified.json";
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct NotifiedLog {
+    last: HashMap<String, DateTime<Utc>>,
+    #[serde(skip)]
+    ephemeral: bool,
+}
+
+impl NotifiedLog {
+    /// Loads the log, a missing or unreadable log is an empty one
+    pub fn load(config: &Config) -> NotifiedLog {
+        if config.ephemeral {
+            return NotifiedLog {
+                ephemeral: true,
+                ..NotifiedLog::default()
+            };
+        }
+
+        fs::read_to_string(state_file_path(NOTIFIED_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether the task identified by `key` was not notified inside the window
+    pub fn should_notify(&self, key: &str, now: DateTime<Utc>, window: Duration) -> bool {
+        self.last.get(key).is_none_or(|last| now - *last >= window)
+    }
+
+    pub fn mark(&mut self, key: String, now: DateTime<Utc>) {
+        self.last.insert(key, now);
+    }
+
+    /// Saves the log dropping the entries older than the window, they would be
+    /// notified again anyway
+    pub fn save(&mut self, now: DateTime<Utc>, window: Duration) -> Result<()> {
+        if self.ephemeral {
+            return Ok(());
+        }
+
+        self.last.retain(|_, last| now - *last < window);
+        let contents =
+            serde_json::to_string_pretty(self).map_err(|_| Error::FailedToUpdateNotifiedLog)?;
+        fs::write(state_file_path(NOTIFIED_FILE), contents).map_err(|e| {
+            log::error!("Error in writing the notified log {e}");
+            Error::FailedToUpdateNotifiedLog
+        })
+    }
+}
+
+/// Shows the desktop notification for an overdue task of `table`
+pub fn send_overdue_notification(table: &str, task: &GetTaskResponseDetail) -> Result<()> {
+    let due = task.due.map_or_else(String::new, |due| {
+        zone().from_server(due).format("%Y-%m-%d %H:%M").to_string()
+    });
+
+    Notification::new()
+        .appname("rsm")
+        .summary(&format!("Overdue: {}", task.description))
+        .body(&format!("{table}, due {due}"))
+        .show()
+        .map(|_| ())
+        .map_err(|e| {
+            log::error!("Error in sending the notification {e}");
+            Error::FailedToNotify
+        })
+}
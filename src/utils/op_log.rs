@@ -0,0 +1,314 @@
+/// # Op Log Module
+///
+/// This module keeps a local log of the last mutating operations so that `undo` can
+/// reverse them.
+///
+/// Every entry stores what is needed to invert the operation: the description of an added
+/// task, the previous values of an updated one, a snapshot of the removed tasks. The log
+/// lives next to the config file, holds at most `MAX_OPS` entries and is never written
+/// when the config comes from the environment.
+///
+/// ## Types
+///
+/// - `Op`: A mutating operation with the data needed to invert it.
+/// - `OpLog`: The log of the last operations.
+///
+/// `Op::undo` sends the requests that invert an operation, an undo that partly fails puts
+/// back what is left of the operation, see `Op::remaining`.
+///
+/// ## Functions
+///
+/// - `record_op`: Records an operation the server accepted.
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::api_list::GetTaskResponseDetail;
+use crate::api::{Api, ErrorResponse, SuccessfulResponse};
+use crate::error::{Error, Result};
+use crate::parsers::Due;
+use crate::utils::batch_report::BatchReport;
+use crate::utils::config_helper::{state_file_path, Config};
+use crate::utils::table_formatter::FormattedResponse;
+
+/// The maximum number of operations kept, the oldest ones are dropped first
+const MAX_OPS: usize = 50;
+
+const OP_LOG_FILE: &str = "oplog.json";
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    /// A task was added, it gets removed
+    Add { table: String, description: String },
+    /// A task was updated, `description` is the new one and `previous` gets restored
+    Update {
+        table: String,
+        description: String,
+        previous: GetTaskResponseDetail,
+    },
+    /// A task was moved to another table, it gets moved back with its `previous` values
+    Move {
+        from: String,
+        to: String,
+        previous: GetTaskResponseDetail,
+    },
+    /// Tasks were removed (one by `remove`, more by `clear`), they get added back
+    Remove {
+        table: String,
+        tasks: Vec<GetTaskResponseDetail>,
+    },
+    /// A table was dropped, it gets created again and its tasks added back
+    Drop {
+        table: String,
+        has_due: bool,
+        tasks: Vec<GetTaskResponseDetail>,
+    },
+}
+
+impl std::fmt::Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Op::Add { table, description } => write!(f, "add '{description}' to {table}"),
+            Op::Update {
+                table, description, ..
+            } => write!(f, "update '{description}' in {table}"),
+            Op::Move { from, to, previous } => {
+                write!(f, "move '{}' from {from} to {to}", previous.description)
+            }
+            Op::Remove { table, tasks } => match tasks.as_slice() {
+                [task] => write!(f, "remove '{}' from {table}", task.description),
+                _ => write!(f, "remove {} tasks from {table}", tasks.len()),
+            },
+            Op::Drop { table, .. } => write!(f, "drop {table}"),
+        }
+    }
+}
+
+impl Op {
+    /// Sends the requests that invert the operation, the outcome of each task is in the
+    /// returned report
+    pub fn undo(&self, api: &Api) -> Result<BatchReport> {
+        let mut report = BatchReport::default();
+
+        match self {
+            Op::Add { table, description } => {
+                let res = api.remove_task(table.clone(), description.clone())?;
+                report.record(description, Ok(res));
+            }
+            Op::Update {
+                table,
+                description,
+                previous,
+            } => {
+                let due = previous.due.map(|due| Due(due).to_string());
+                let res =
+                    api.update_task(table.clone(), description.clone(), previous.body(&due))?;
+                report.record(&previous.description, Ok(res));
+            }
+            Op::Remove { table, tasks } => {
+                for task in tasks {
+                    let due = task.due.map(|due| Due(due).to_string());
+                    let res = api.add_task(table.clone(), task.body(&due));
+                    report.record(&task.description, res);
+                }
+            }
+            Op::Move { from, to, previous } => {
+                let due = previous.due.map(|due| Due(due).to_string());
+                let res = api.move_task(to.clone(), from.clone(), previous.body(&due))?;
+                report.record(&previous.description, Ok(res));
+            }
+            Op::Drop {
+                table,
+                has_due,
+                tasks,
+            } => {
+                let res = api.create_table(table.clone(), *has_due, true)?;
+                if let Some(err) = res.as_any().downcast_ref::<ErrorResponse>() {
+                    return Err(err.clone().into());
+                }
+                for task in tasks {
+                    let due = task.due.map(|due| Due(due).to_string());
+                    let res = api.add_task(table.clone(), task.body(&due));
+                    report.record(&task.description, res);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Returns what is left to undo after the undo described by `report`, `None` when nothing
+    /// failed. Only the tasks that couldn't be added back are kept, and a dropped table that
+    /// was created again only misses them
+    pub fn remaining(self, report: &BatchReport) -> Option<Op> {
+        if report.failed() == 0 {
+            return None;
+        }
+
+        // a task is added back per recorded item, in order
+        let failed = |tasks: Vec<GetTaskResponseDetail>| -> Vec<GetTaskResponseDetail> {
+            tasks
+                .into_iter()
+                .zip(report.items())
+                .filter(|(_, item)| !item.ok)
+                .map(|(task, _)| task)
+                .collect()
+        };
+        Some(match self {
+            Op::Remove { table, tasks } | Op::Drop { table, tasks, .. } => Op::Remove {
+                table,
+                tasks: failed(tasks),
+            },
+            op => op,
+        })
+    }
+}
+
+/// Records a mutating operation in the op log if the server accepted it
+///
+/// # Args
+/// - config: struct `Config` that represents the config management
+/// - res: the response of the operation
+/// - op: the operation to record
+pub fn record_op(config: &Config, res: &dyn FormattedResponse, op: Op) {
+    if res.as_any().is::<SuccessfulResponse>() {
+        OpLog::load(config).push(op);
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct OpLog {
+    ops: Vec<Op>,
+    #[serde(skip)]
+    ephemeral: bool,
+}
+
+impl OpLog {
+    /// Loads the log, a missing or unreadable log is an empty one
+    pub fn load(config: &Config) -> OpLog {
+        if config.ephemeral {
+            return OpLog {
+                ephemeral: true,
+                ..OpLog::default()
+            };
+        }
+
+        fs::read_to_string(state_file_path(OP_LOG_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Appends an operation and saves the log, a failure is only logged since the
+    /// operation itself already succeeded
+    pub fn push(&mut self, op: Op) {
+        self.ops.push(op);
+        if self.ops.len() > MAX_OPS {
+            self.ops.drain(..self.ops.len() - MAX_OPS);
+        }
+
+        if let Err(e) = self.save() {
+            log::error!("Failed to record the operation: {e:?}");
+        }
+    }
+
+    /// Removes the last operation and saves the log
+    pub fn pop(&mut self) -> Result<Op> {
+        let op = self.ops.pop().ok_or(Error::NothingToUndo)?;
+        self.save()?;
+        Ok(op)
+    }
+
+    fn save(&self) -> Result<()> {
+        if self.ephemeral {
+            return Ok(());
+        }
+
+        let contents =
+            serde_json::to_string_pretty(self).map_err(|_| Error::FailedToUpdateOpLog)?;
+        fs::write(state_file_path(OP_LOG_FILE), contents).map_err(|e| {
+            log::error!("Error in writing the op log {e}");
+            Error::FailedToUpdateOpLog
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(description: &str) -> GetTaskResponseDetail {
+        GetTaskResponseDetail {
+            description: description.to_owned(),
+            group: String::new(),
+            due: None,
+            note: None,
+            recurrence: None,
+            completed: false,
+        }
+    }
+
+    fn descriptions(op: &Op) -> Vec<&str> {
+        match op {
+            Op::Remove { tasks, .. } => tasks.iter().map(|t| t.description.as_str()).collect(),
+            _ => panic!("expected a removal"),
+        }
+    }
+
+    #[test]
+    fn an_undo_without_failures_leaves_nothing() {
+        let mut report = BatchReport::default();
+        report.success("buy milk");
+        let op = Op::Add {
+            table: "todo".to_owned(),
+            description: "buy milk".to_owned(),
+        };
+
+        assert!(op.remaining(&report).is_none());
+    }
+
+    #[test]
+    fn a_failed_undo_keeps_the_whole_single_operation() {
+        let mut report = BatchReport::default();
+        report.failure("buy milk", "SERVER_ERROR");
+        let op = Op::Add {
+            table: "todo".to_owned(),
+            description: "buy milk".to_owned(),
+        };
+
+        assert!(matches!(op.remaining(&report), Some(Op::Add { .. })));
+    }
+
+    #[test]
+    fn a_partly_undone_removal_keeps_the_failed_tasks() {
+        let mut report = BatchReport::default();
+        report.success("a");
+        report.failure("b", "SERVER_ERROR");
+        report.success("c");
+        let op = Op::Remove {
+            table: "todo".to_owned(),
+            tasks: vec![task("a"), task("b"), task("c")],
+        };
+
+        assert_eq!(descriptions(&op.remaining(&report).unwrap()), ["b"]);
+    }
+
+    #[test]
+    fn a_partly_undone_drop_only_adds_back_the_failed_tasks() {
+        let mut report = BatchReport::default();
+        report.failure("a", "SERVER_ERROR");
+        report.success("b");
+        let op = Op::Drop {
+            table: "work".to_owned(),
+            has_due: false,
+            tasks: vec![task("a"), task("b")],
+        };
+
+        // the table exists again, it must not be created twice
+        let remaining = op.remaining(&report).unwrap();
+        assert!(matches!(&remaining, Op::Remove { table, .. } if table == "work"));
+        assert_eq!(descriptions(&remaining), ["a"]);
+    }
+}
@@ -0,0 +1,204 @@
+/// # Output Module
+///
+/// This module decides how the results of the commands are shown to the user.
+///
+/// ## Types
+///
+/// - `OutputMode`: Either the human readable output or the machine readable one (`--json`),
+///   in the latter only compact json goes to stdout, one document per result.
+///
+/// ## Functions
+///
+/// - `print_response`: Prints a response from the server according to the output mode.
+/// - `set_output_file`: Redirects the rendered results to a file (`--output`).
+/// - `emit`: Writes a rendered result to stdout or to the output file.
+/// - `set_color`: Turns the colors off for the whole run (`--no-color`, `NO_COLOR` or no tty).
+/// - `colored`: Returns a text with its colors only when they are on.
+/// - `flush_output`: Writes the collected results to the output file, if one is set.
+/// - `set_print_request_id`: Reports the request id of the successful answers too
+///   (`--print-request-id`).
+/// - `report_request_id`: Writes the request id of an answer of the server on stderr.
+/// - `set_quiet`: Drops the results meant for stdout for the whole run (`--quiet`).
+/// - `is_quiet`: Whether the results meant for stdout are dropped.
+/// - `set_spinner`: Turns the spinner off for the whole run (`--json`, `--quiet`, `--output`).
+/// - `spinner_enabled`: Whether the spinner can be shown.
+use std::{
+    borrow::Cow,
+    fs,
+    path::PathBuf,
+    process::ExitCode,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::api::ErrorResponse;
+use crate::error::{Error, Result};
+use crate::utils::table_formatter::FormattedResponse;
+
+static OUTPUT_FILE: OnceLock<PathBuf> = OnceLock::new();
+static OUTPUT_BUFFER: Mutex<String> = Mutex::new(String::new());
+static COLOR: OnceLock<bool> = OnceLock::new();
+static PRINT_REQUEST_ID: OnceLock<bool> = OnceLock::new();
+static QUIET: OnceLock<bool> = OnceLock::new();
+static SPINNER: OnceLock<bool> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputMode {
+    Human,
+    Json,
+}
+
+impl OutputMode {
+    #[cfg(feature = "cli")]
+    pub fn from_args(args: &clap::ArgMatches) -> OutputMode {
+        if args.get_flag("json") {
+            OutputMode::Json
+        } else {
+            OutputMode::Human
+        }
+    }
+
+    /// Reports an error that made the command fail on stderr, as a json object in json
+    /// mode, and returns the exit code of its category (see `Error::exit_code`)
+    pub fn report_error(self, err: Error) -> ExitCode {
+        match self {
+            OutputMode::Human => {
+                eprintln!("Error: {err}");
+                if let Error::ApiError { req_uuid, .. } = &err {
+                    report_request_id(req_uuid, true);
+                }
+            }
+            OutputMode::Json => eprintln!("{}", err.to_json()),
+        }
+        ExitCode::from(err.exit_code())
+    }
+}
+
+/// Prints a response from the server, in json mode it is printed as compact json. In json
+/// mode and with `--quiet` an `ErrorResponse` is turned into an `Error` so that it is reported
+/// on stderr with a failing exit code
+pub fn print_response(res: &dyn FormattedResponse, output: OutputMode) -> Result<()> {
+    if output == OutputMode::Json || is_quiet() {
+        if let Some(err) = res.as_any().downcast_ref::<ErrorResponse>() {
+            return Err(Error::ApiError {
+                error_type: err.error.error_type.clone(),
+                req_uuid: err.error.req_uuid.clone(),
+            });
+        }
+    }
+
+    match output {
+        OutputMode::Human => res.print(),
+        OutputMode::Json => emit(&format!("{}\n", res.to_json())),
+    }
+    Ok(())
+}
+
+/// Redirects the rendered results to `path` for the whole run, calls after the first
+/// one are ignored
+pub fn set_output_file(path: PathBuf) {
+    let _ = OUTPUT_FILE.set(path);
+}
+
+/// Turns the colors on or off for the whole run, calls after the first one are ignored
+pub fn set_color(enabled: bool) {
+    let _ = COLOR.set(enabled);
+}
+
+/// Reports the request id of the successful answers too for the whole run, calls after the
+/// first one are ignored
+pub fn set_print_request_id(enabled: bool) {
+    let _ = PRINT_REQUEST_ID.set(enabled);
+}
+
+/// Writes the request id the server gave to an answer on stderr as the last line, so that it
+/// can be pasted in a bug report. The ids of failed requests are always written, the ones of
+/// successful requests only with `--print-request-id`
+pub fn report_request_id(req_uuid: &str, failed: bool) {
+    if failed || PRINT_REQUEST_ID.get().copied().unwrap_or(false) {
+        eprintln!("(server request id: {req_uuid})");
+    }
+}
+
+/// Drops the results meant for stdout for the whole run, the ones written to the output file
+/// are kept. Calls after the first one are ignored
+pub fn set_quiet(enabled: bool) {
+    let _ = QUIET.set(enabled);
+}
+
+/// Whether the results meant for stdout are dropped (`--quiet`)
+pub fn is_quiet() -> bool {
+    QUIET.get().copied().unwrap_or(false)
+}
+
+/// Turns the spinner on or off for the whole run, calls after the first one are ignored
+pub fn set_spinner(enabled: bool) {
+    let _ = SPINNER.set(enabled);
+}
+
+/// Whether the spinner can be shown, it is still only drawn on a terminal
+pub fn spinner_enabled() -> bool {
+    SPINNER.get().copied().unwrap_or(true)
+}
+
+/// Returns the text unchanged when the colors are on and without them otherwise
+pub fn colored(text: &str) -> Cow<'_, str> {
+    match COLOR.get().copied().unwrap_or(true) {
+        true => Cow::Borrowed(text),
+        false => Cow::Owned(strip_colors(text)),
+    }
+}
+
+/// Writes a rendered result, it goes to stdout unless an output file is set, in that
+/// case it is collected and written by `flush_output`. With `--quiet` stdout gets nothing
+pub fn emit(text: &str) {
+    if OUTPUT_FILE.get().is_none() {
+        if !is_quiet() {
+            print!("{}", colored(text));
+        }
+        return;
+    }
+
+    if let Ok(mut buffer) = OUTPUT_BUFFER.lock() {
+        buffer.push_str(&strip_colors(text));
+    }
+}
+
+/// Removes the ansi color sequences (`\x1b[..m`), they only make sense on a terminal
+pub(crate) fn strip_colors(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| *c == 'm');
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Writes the collected results to the output file creating its parent directories,
+/// the number of bytes written is reported on stderr
+pub fn flush_output() -> Result<()> {
+    let Some(path) = OUTPUT_FILE.get() else {
+        return Ok(());
+    };
+    let buffer = OUTPUT_BUFFER.lock().map_err(|_| Error::RsmFailed)?;
+
+    let write = || -> std::io::Result<()> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, buffer.as_bytes())
+    };
+    write().map_err(|e| Error::FailedToWriteOutput {
+        detail: e.to_string(),
+    })?;
+
+    log::info!("Wrote {} bytes to {}", buffer.len(), path.display());
+    eprintln!("Wrote {} bytes to {}", buffer.len(), path.display());
+    Ok(())
+}
@@ -0,0 +1,149 @@
+/// # Table Cache Module
+///
+/// This module keeps the characteristics of the tables (e.g. whether they have a due) in a
+/// small state file next to the config, so that they can be checked before a request without
+/// asking the server every time.
+///
+/// The cache is filled every time the tables are listed, it is stale after `TABLE_CACHE_TTL`
+/// and dropped after a table is created or dropped.
+///
+/// ## Types
+///
+/// - `TableCache`: The tables as last fetched from the server.
+use std::{fs, io::ErrorKind};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::api::api_list::{TableCharacteristicsResponse, TableCharacteristicsResponseDetails};
+use crate::utils::config_helper::state_file_path;
+
+const TABLE_CACHE_FILE: &str = "tables.json";
+/// How long the cached tables are used before fetching them again
+pub const TABLE_CACHE_TTL: Duration = Duration::hours(1);
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct TableCache {
+    tables: Vec<TableCharacteristicsResponseDetails>,
+    /// When the tables were fetched, a cache without it is stale
+    #[serde(default)]
+    fetched_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    ephemeral: bool,
+}
+
+impl TableCache {
+    /// Loads the cache, a missing or unreadable cache is an empty one. An ephemeral cache
+    /// (for the configs that never touch the disk) is always empty and never saved
+    pub fn load(ephemeral: bool) -> TableCache {
+        if ephemeral {
+            return TableCache {
+                ephemeral: true,
+                ..TableCache::default()
+            };
+        }
+
+        fs::read_to_string(state_file_path(TABLE_CACHE_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the cached tables if they were fetched less than `ttl` before `now`
+    pub fn fresh_tables(
+        &self,
+        now: DateTime<Utc>,
+        ttl: Duration,
+    ) -> Option<&[TableCharacteristicsResponseDetails]> {
+        self.fetched_at
+            .filter(|fetched_at| now - *fetched_at < ttl)
+            .map(|_| self.tables.as_slice())
+    }
+
+    /// Replaces the cached tables with the ones fetched at `now` and saves them, the cache
+    /// is only a shortcut so failing to save it is logged and ignored
+    pub fn store(&mut self, tables: &TableCharacteristicsResponse, now: DateTime<Utc>) {
+        self.tables = tables.res.clone();
+        self.fetched_at = Some(now);
+        if self.ephemeral {
+            return;
+        }
+
+        let saved = serde_json::to_string_pretty(self)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                fs::write(state_file_path(TABLE_CACHE_FILE), contents).map_err(|e| e.to_string())
+            });
+        if let Err(e) = saved {
+            log::warn!("Error in writing the table cache {e}");
+        }
+    }
+
+    /// Drops the cache, the tables are fetched again the next time they are needed
+    pub fn invalidate(&mut self) {
+        self.tables.clear();
+        self.fetched_at = None;
+        if self.ephemeral {
+            return;
+        }
+
+        match fs::remove_file(state_file_path(TABLE_CACHE_FILE)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                log::warn!("Error in removing the table cache {e}");
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetched_at() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2030-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    /// An ephemeral cache holding the `todo` table, so that the tests never touch the disk
+    fn cache() -> TableCache {
+        let mut cache = TableCache::load(true);
+        let tables = TableCharacteristicsResponse {
+            res: vec![TableCharacteristicsResponseDetails {
+                has_due: false,
+                name: "todo".to_owned(),
+                tasks: None,
+            }],
+        };
+        cache.store(&tables, fetched_at());
+        cache
+    }
+
+    #[test]
+    fn the_tables_are_fresh_until_the_ttl() {
+        let cache = cache();
+        let fresh = |elapsed| cache.fresh_tables(fetched_at() + elapsed, TABLE_CACHE_TTL);
+
+        assert_eq!(fresh(Duration::zero()).unwrap()[0].name, "todo");
+        assert!(fresh(TABLE_CACHE_TTL - Duration::seconds(1)).is_some());
+        assert!(fresh(TABLE_CACHE_TTL).is_none());
+        assert!(fresh(Duration::days(1)).is_none());
+    }
+
+    #[test]
+    fn a_cache_never_fetched_is_stale() {
+        assert!(TableCache::load(true)
+            .fresh_tables(fetched_at(), TABLE_CACHE_TTL)
+            .is_none());
+    }
+
+    #[test]
+    fn an_invalidated_cache_is_stale() {
+        let mut cache = cache();
+
+        cache.invalidate();
+
+        assert!(cache.fresh_tables(fetched_at(), TABLE_CACHE_TTL).is_none());
+    }
+}
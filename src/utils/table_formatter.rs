@@ -2,54 +2,143 @@
 ///
 /// This module implements formatting for mutliple types to make
 /// the CLI output be pretty formatted.
-use crate::api::api_list::{GetTaskResponse, TableCharacteristicsResponse};
+use crate::api::api_list::{
+    GetTaskResponse, GetTaskResponseDetail, TableCharacteristicsResponse, TaskListing, TaskStats,
+};
 use crate::api::{ErrorResponse, SuccessfulResponse};
+use crate::parsers::Recurrence;
+use crate::utils::config_helper::ConfigCheck;
+use crate::utils::output::{colored, emit, report_request_id};
+use crate::utils::timezone::{server_now, zone, Zone};
+use chrono::{NaiveDateTime, TimeDelta};
 use std::fmt::Display;
 
+/// The widest a task description gets in a table, longer ones are truncated
+const MAX_DESCRIPTION_WIDTH: usize = 60;
+
 // -- Custom trait impl
 pub trait FormattedResponse {
     fn print(&self);
 
     fn as_any(&self) -> &dyn std::any::Any;
+
+    /// The response as json, printed instead of `print` with `--json`
+    fn to_json(&self) -> serde_json::Value;
 }
 
 impl FormattedResponse for GetTaskResponse {
     fn print(&self) {
-        println!("{}", self);
+        emit(&format!("{}\n", self));
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!(self)
+    }
 }
 
 impl FormattedResponse for TableCharacteristicsResponse {
     fn print(&self) {
-        println!("{}", self);
+        emit(&format!("{}\n", self));
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!(self)
+    }
+}
+
+impl FormattedResponse for TaskStats {
+    fn print(&self) {
+        emit(&self.to_string());
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!(self)
+    }
+}
+
+impl FormattedResponse for ConfigCheck {
+    fn print(&self) {
+        emit(&self.to_string());
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!(self)
+    }
+}
+
+// a listing prints and downcasts as what it holds
+impl FormattedResponse for TaskListing {
+    fn print(&self) {
+        match self {
+            TaskListing::Tasks(tasks) => tasks.print(),
+            TaskListing::Tables(tables) => tables.print(),
+            TaskListing::ApiError(err) => err.print(),
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        match self {
+            TaskListing::Tasks(tasks) => tasks,
+            TaskListing::Tables(tables) => tables,
+            TaskListing::ApiError(err) => err,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            TaskListing::Tasks(tasks) => tasks.to_json(),
+            TaskListing::Tables(tables) => tables.to_json(),
+            TaskListing::ApiError(err) => err.to_json(),
+        }
+    }
 }
 
 impl FormattedResponse for ErrorResponse {
     fn print(&self) {
-        println!("{}", self);
+        // stdout only gets results, the errors go to stderr like the other failures
+        eprintln!("{}", colored(&self.to_string()));
+        report_request_id(&self.error.req_uuid, true);
     }
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!(self)
+    }
 }
 
 impl FormattedResponse for SuccessfulResponse {
     fn print(&self) {
-        println!("{}", self);
+        emit(&format!("{}\n", self));
+        if let Some(req_uuid) = &self.req_uuid {
+            report_request_id(req_uuid, false);
+        }
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!(self)
+    }
 }
 
 // -- Display impl
@@ -68,6 +157,8 @@ impl Display for ErrorResponse {
             f,
             "+ - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - +"
         )?;
+        writeln!(f, "{}", self.error.error_type.hint())?;
+        writeln!(f, "\x1b[90m{}\x1b[0m", self.summary())?;
         Ok(())
     }
 }
@@ -105,80 +196,655 @@ impl Display for SuccessfulResponse {
 }
 impl std::fmt::Display for GetTaskResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.res.iter().all(|res| res.due.is_none()) {
-            writeln!(
-                f,
-                "+ ---------------------------------------------------------------------------------------------- + ------------------------ +"
-            )?;
-            writeln!(
-                f,
-                "| \x1b[34mTASK\x1b[0m {:<89} | \x1b[34mGROUP\x1b[0m                    |",
-                " "
-            )?; // Due and group columns inverted
-            writeln!(
-                f,
-                "+ ---------------------------------------------------------------------------------------------- + ------------------------ +"
-            )?;
-            for detail in &self.res {
-                writeln!(
-                    f,
-                    "| {:<95}| {:<25}|",
-                    detail.description,
-                    detail.group, // Group now printed before due
-                )?;
+        self.write_table(f, server_now(), zone())
+    }
+}
+
+impl GetTaskResponse {
+    /// Writes the task table, the dues are relative to `now` (as the server stores it) or
+    /// shown as dates in `zone`
+    fn write_table(
+        &self,
+        f: &mut impl std::fmt::Write,
+        now: NaiveDateTime,
+        zone: Zone,
+    ) -> std::fmt::Result {
+        if self.res.is_empty() {
+            return match self.total {
+                // a page past the end of the table
+                Some(total) if self.offset > 0 => {
+                    writeln!(f, "No items on this page, the table has {total}.")
+                }
+                _ => writeln!(f, "No items."),
+            };
+        }
+
+        // the due column is only shown when at least a task has one
+        let has_due = self.res.iter().any(|res| res.due.is_some());
+        let mut headers = vec!["TASK".to_owned(), "GROUP".to_owned()];
+        if has_due {
+            // the dates depend on the zone they are shown in, the relative dues don't
+            headers.push(match self.absolute_dues {
+                true => format!("DUE ({zone})"),
+                false => "DUE".to_owned(),
+            });
+        }
+
+        // every line has its cells and the color of the row
+        let mut lines: Vec<(Vec<String>, &str)> = vec![];
+        for detail in &self.res {
+            let mut cells = vec![
+                truncate(&detail.description, MAX_DESCRIPTION_WIDTH),
+                detail.group.clone(),
+            ];
+            if has_due {
+                cells.push(detail.due.map_or_else(String::new, |due| {
+                    match self.absolute_dues {
+                        true => zone
+                            .from_server(due)
+                            .format("%Y-%m-%d %H:%M:%S")
+                            .to_string(),
+                        false => short_relative_time(due - now),
+                    }
+                }));
             }
-            writeln!(
-                f,
-                "+ ---------------------------------------------------------------------------------------------- + ------------------------ +"
-            )?;
-        } else {
-            writeln!(
-                f,
-                "+ ------------------------------------------------------------------------------- + ------------------- + ------------------- +"
-            )?;
-            writeln!(
-                f,
-                "| \x1b[34mTASK\x1b[0m {:<74} | \x1b[34mGROUP\x1b[0m               | \x1b[34mDUE\x1b[0m                 |",
-                " "
-            )?; // Due and group columns inverted
-            writeln!(
-                f,
-                "+ ------------------------------------------------------------------------------- + ------------------- + ------------------- +"
-            )?;
-            for detail in &self.res {
+            lines.push((cells, due_color(detail, now)));
+
+            if let Some(note) = &detail.note {
+                let mut cells = vec![format!(
+                    "  note: {}",
+                    truncate(note, MAX_DESCRIPTION_WIDTH - 8)
+                )];
+                cells.resize(headers.len(), String::new());
+                lines.push((cells, ""));
+            }
+        }
+
+        // every column is as wide as its longest cell
+        let widths: Vec<usize> = headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                lines
+                    .iter()
+                    .map(|(cells, _)| cells[i].chars().count())
+                    .fold(header.len(), usize::max)
+            })
+            .collect();
+
+        let border: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        let border = format!("+ {} +", border.join(" + "));
+        let header: Vec<String> = headers
+            .iter()
+            .zip(&widths)
+            .map(|(header, width)| {
+                format!(
+                    "\x1b[34m{header}\x1b[0m{}",
+                    " ".repeat(width - header.len())
+                )
+            })
+            .collect();
+
+        writeln!(f, "{border}")?;
+        writeln!(f, "| {} |", header.join(" | "))?;
+        writeln!(f, "{border}")?;
+        for (cells, color) in &lines {
+            let row: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| match color.is_empty() {
+                    true => format!("{cell:<width$}"),
+                    false => format!("{color}{cell:<width$}\x1b[0m"),
+                })
+                .collect();
+            writeln!(f, "| {} |", row.join(" | "))?;
+        }
+        writeln!(f, "{border}")?;
+        write_count_footer(f, self, now)?;
+        Ok(())
+    }
+}
+
+// -- Detail view
+impl std::fmt::Display for GetTaskResponseDetail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "\x1b[34m{:<13}\x1b[0m{}",
+            "description", self.description
+        )?;
+        writeln!(f, "\x1b[34m{:<13}\x1b[0m{}", "group", self.group)?;
+        match self.due {
+            Some(due) => {
+                let now = server_now();
                 writeln!(
                     f,
-                    "| {:<80}| {:<20}| {:<20}|",
-                    detail.description,
-                    detail.group, // Group now printed before due
-                    detail.due.map_or_else(
-                        || "None".to_string(),
-                        |due| due.format("%Y-%m-%d %H:%M:%S").to_string()
-                    ),
+                    "\x1b[34m{:<13}\x1b[0m{} {} ({})",
+                    "due",
+                    zone().from_server(due).format("%Y-%m-%d %H:%M"),
+                    zone(),
+                    relative_time(due - now)
                 )?;
             }
-            writeln!(
-                f,
-                "+ ------------------------------------------------------------------------------- + ------------------- + ------------------- +"
-            )?;
+            None => writeln!(f, "\x1b[34m{:<13}\x1b[0mNone", "due")?,
+        }
+        if let Some(recurrence) = &self.recurrence {
+            // rules the client doesn't know are shown as the server sent them
+            let described = recurrence
+                .parse::<Recurrence>()
+                .map_or_else(|_| recurrence.clone(), |rule| rule.describe());
+            writeln!(f, "\x1b[34m{:<13}\x1b[0m{}", "repeats", described)?;
+        }
+        writeln!(
+            f,
+            "\x1b[34m{:<13}\x1b[0m{}",
+            "completed",
+            if self.completed { "yes" } else { "no" }
+        )?;
+        if let Some(note) = &self.note {
+            writeln!(f, "\x1b[34mnote\x1b[0m")?;
+            for line in note.lines() {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Describes how far a moment is from now, e.g. "in 3 days" or "2 hours ago",
+/// only the biggest unit is kept
+fn relative_time(delta: TimeDelta) -> String {
+    let minutes = delta.num_minutes().abs();
+    let (amount, unit) = match minutes {
+        0 => return "now".to_owned(),
+        m if m < 60 => (m, "minute"),
+        m if m < 60 * 24 => (m / 60, "hour"),
+        m => (m / (60 * 24), "day"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+
+    if delta > TimeDelta::zero() {
+        format!("in {amount} {unit}{plural}")
+    } else {
+        format!("{amount} {unit}{plural} ago")
+    }
+}
+
+/// Describes how far a due is from now in the short form of the task table, e.g. "in 2h",
+/// "in 1h 30m" or "5m ago", rounded to the minute and keeping at most two units
+fn short_relative_time(delta: TimeDelta) -> String {
+    let minutes = (delta.num_seconds().abs() + 30) / 60;
+    let (days, hours, minutes) = (minutes / (60 * 24), minutes / 60 % 24, minutes % 60);
+    let amount = match (days, hours, minutes) {
+        (0, 0, 0) => return "now".to_owned(),
+        (0, 0, m) => format!("{m}m"),
+        (0, h, 0) => format!("{h}h"),
+        (0, h, m) => format!("{h}h {m}m"),
+        // past a week the hours don't matter anymore
+        (d, 0, _) => format!("{d}d"),
+        (d, _, _) if d >= 7 => format!("{d}d"),
+        (d, h, _) => format!("{d}d {h}h"),
+    };
+
+    if delta > TimeDelta::zero() {
+        format!("in {amount}")
+    } else {
+        format!("{amount} ago")
+    }
+}
+
+// -- Stats
+impl std::fmt::Display for TaskStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "\x1b[34m{:<11}\x1b[0m{}", "total", self.total)?;
+        writeln!(f, "\x1b[34m{:<11}\x1b[0m{}", "overdue", self.overdue)?;
+        writeln!(f, "\x1b[34m{:<11}\x1b[0m{}", "due today", self.due_today)?;
+        if self.by_group.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f, "\x1b[34mgroups\x1b[0m")?;
+        let width = self
+            .by_group
+            .keys()
+            .map(|group| group.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (group, count) in &self.by_group {
+            writeln!(f, "  {group:<width$}  {count}")?;
         }
         Ok(())
     }
 }
 
+impl std::fmt::Display for ConfigCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "\x1b[34m{:<10}\x1b[0m{}", "path", self.path)?;
+        if let Some(profile) = &self.active_profile {
+            writeln!(f, "\x1b[34m{:<10}\x1b[0m{}", "profile", profile)?;
+            let backend = self.backend.as_deref().unwrap_or("built-in");
+            writeln!(f, "\x1b[34m{:<10}\x1b[0m{}", "backend", backend)?;
+            let session = match (self.logged_in, self.token_expires) {
+                (false, _) => "not logged in".to_owned(),
+                (true, None) => "no expiry".to_owned(),
+                (true, Some(expires)) => {
+                    format!("expires {}", expires.format("%Y-%m-%d %H:%M UTC"))
+                }
+            };
+            writeln!(f, "\x1b[34m{:<10}\x1b[0m{}", "session", session)?;
+        }
+
+        if self.problems.is_empty() {
+            return writeln!(f, "\x1b[32mno problems found\x1b[0m");
+        }
+        writeln!(f, "\x1b[31mproblems\x1b[0m")?;
+        for problem in &self.problems {
+            writeln!(f, "  - {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+// -- Markdown
+impl GetTaskResponse {
+    /// Renders the tasks as a GitHub flavored markdown table, the due column is only
+    /// there for tables with due. With `checklist` every task becomes a `- [ ]` item,
+    /// ticked when it is completed
+    pub fn to_markdown(&self, checklist: bool) -> String {
+        let with_due = self.res.iter().any(|task| task.due.is_some());
+        let format_due = |task: &GetTaskResponseDetail| {
+            task.due.map_or_else(String::new, |due| {
+                zone().from_server(due).format("%Y-%m-%d %H:%M").to_string()
+            })
+        };
+
+        let mut out = String::new();
+        if checklist {
+            for task in &self.res {
+                let mark = if task.completed { "x" } else { " " };
+                out.push_str(&format!(
+                    "- [{mark}] {}",
+                    task.description.replace('\n', " ")
+                ));
+                if task.due.is_some() {
+                    out.push_str(&format!(" (due {})", format_due(task)));
+                }
+                out.push('\n');
+            }
+            return out;
+        }
+
+        if with_due {
+            out.push_str("| description | group | due |\n| --- | --- | --- |\n");
+        } else {
+            out.push_str("| description | group |\n| --- | --- |\n");
+        }
+        for task in &self.res {
+            out.push_str(&format!(
+                "| {} | {} |",
+                escape_markdown_cell(&task.description),
+                escape_markdown_cell(&task.group)
+            ));
+            if with_due {
+                out.push_str(&format!(" {} |", format_due(task)));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the tasks as csv with a `description,group,due` header, the due is in
+    /// ISO 8601 and empty for the tasks without one
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("description,group,due\n");
+        for task in &self.res {
+            let due = task.due.map_or_else(String::new, |due| {
+                zone()
+                    .from_server(due)
+                    .format("%Y-%m-%dT%H:%M:%S")
+                    .to_string()
+            });
+            out.push_str(&format!(
+                "{},{},{}\n",
+                escape_csv_field(&task.description),
+                escape_csv_field(&task.group),
+                due
+            ));
+        }
+        out
+    }
+}
+
+/// Quotes a csv field when it contains a comma, a quote or a newline, the quotes
+/// inside get doubled
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Escapes the pipes of a markdown table cell, newlines would break the row so
+/// they become spaces
+fn escape_markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Returns the color of the row of a task: red when overdue, yellow when due within
+/// the next hour, none otherwise or without a due
+fn due_color(task: &GetTaskResponseDetail, now: NaiveDateTime) -> &'static str {
+    match task.due {
+        Some(_) if task.is_overdue(now) => "\x1b[31m",
+        Some(due) if due - now <= TimeDelta::hours(1) => "\x1b[33m",
+        _ => "",
+    }
+}
+
+/// Cuts a text to `width` chars ending it with an ellipsis, newlines become spaces
+/// so the text always fits a single row
+fn truncate(text: &str, width: usize) -> String {
+    let text = text.replace('\n', " ");
+    if text.chars().count() <= width {
+        return text;
+    }
+    let mut truncated: String = text.chars().take(width - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Writes the "N items (M overdue)" summary under a task table, the overdue
+/// count (at `now`) is only shown for tables with due. For a page of a table whose total is
+/// known it is "showing N–M of T" instead
+fn write_count_footer(
+    f: &mut impl std::fmt::Write,
+    tasks: &GetTaskResponse,
+    now: NaiveDateTime,
+) -> std::fmt::Result {
+    let count = tasks.res.len();
+    let summary = match tasks.total {
+        Some(total) => format!(
+            "showing {}–{} of {total}",
+            tasks.offset + 1,
+            tasks.offset + count
+        ),
+        None if count == 1 => format!("{count} item"),
+        None => format!("{count} items"),
+    };
+
+    if tasks.res.iter().any(|task| task.due.is_some()) {
+        let overdue = tasks.res.iter().filter(|task| task.is_overdue(now)).count();
+        writeln!(f, "{summary} ({overdue} overdue)")
+    } else {
+        writeln!(f, "{summary}")
+    }
+}
+
 impl std::fmt::Display for TableCharacteristicsResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "+ ------------------------------- + ------------- +")?;
-        writeln!(
+        if self.res.is_empty() {
+            return writeln!(f, "No tables.");
+        }
+
+        let with_counts = self.res.iter().any(|table| table.tasks.is_some());
+        // the name column grows with the longest name but never shrinks under the header
+        let name_width = self
+            .res
+            .iter()
+            .map(|table| table.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(31);
+        let due_width = 13;
+        let count_width = 7;
+
+        let mut separator = format!("+ {} + {} +", "-".repeat(name_width), "-".repeat(due_width));
+        if with_counts {
+            separator.push_str(&format!(" {} +", "-".repeat(count_width)));
+        }
+
+        writeln!(f, "{}", separator)?;
+        write!(
             f,
-            "| \x1b[34mTABLE NAME\x1b[0m {:<20} | \x1b[34mSUPPORTS DUE\x1b[0m  |",
-            " "
+            "| \x1b[34m{:<name_width$}\x1b[0m | \x1b[34m{:<due_width$}\x1b[0m |",
+            "TABLE NAME", "SUPPORTS DUE"
         )?;
-        writeln!(f, "| ------------------------------- | ------------- |")?;
+        if with_counts {
+            write!(f, " \x1b[34m{:<count_width$}\x1b[0m |", "TASKS")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}", separator.replace('+', "|"))?;
         for table in &self.res {
-            writeln!(f, "| {:<32}| {:<14}|", table.name, table.has_due)?;
+            write!(
+                f,
+                "| {:<name_width$} | {:<due_width$} |",
+                table.name,
+                if table.has_due { "yes" } else { "no" }
+            )?;
+            if with_counts {
+                let count = table
+                    .tasks
+                    .map_or_else(|| "?".to_string(), |count| count.to_string());
+                write!(f, " {:<count_width$} |", count)?;
+            }
+            writeln!(f)?;
         }
-        writeln!(f, "+ ------------------------------- + ------------- +")?;
+        writeln!(f, "{}", separator)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::output::strip_colors;
+    use chrono::NaiveDate;
+
+    fn task(description: &str, group: &str, due: Option<NaiveDateTime>) -> GetTaskResponseDetail {
+        GetTaskResponseDetail {
+            description: description.to_owned(),
+            group: group.to_owned(),
+            due,
+            note: None,
+            recurrence: None,
+            completed: false,
+        }
+    }
+
+    /// Splits csv into its records and fields, the quoted fields may hold commas, doubled
+    /// quotes and newlines
+    fn parse_csv(csv: &str) -> Vec<Vec<String>> {
+        let mut records = vec![];
+        let mut record = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = csv.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                ('"', _) => quoted = !quoted,
+                (',', false) => record.push(std::mem::take(&mut field)),
+                ('\n', false) => {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                (c, _) => field.push(c),
+            }
+        }
+        records
+    }
+
+    #[test]
+    fn csv_round_trips_the_tasks() {
+        let due = NaiveDate::from_ymd_opt(2030, 6, 2)
+            .unwrap()
+            .and_hms_opt(18, 30, 0)
+            .unwrap();
+        let tasks = GetTaskResponse {
+            res: vec![
+                task("buy milk, eggs", "home", Some(due)),
+                task("read \"Dune\"", "books", None),
+                task("call mom\nthen dad", "family, close", None),
+                task("plain", "", None),
+            ],
+            ..GetTaskResponse::default()
+        };
+
+        let records = parse_csv(&tasks.to_csv());
+
+        assert_eq!(records[0], ["description", "group", "due"]);
+        assert_eq!(records.len(), tasks.res.len() + 1);
+        for (record, task) in records[1..].iter().zip(&tasks.res) {
+            assert_eq!(record[0], task.description);
+            assert_eq!(record[1], task.group);
+            let parsed_due = match record[2].as_str() {
+                "" => None,
+                due => Some(
+                    zone()
+                        .to_server(NaiveDateTime::parse_from_str(due, "%Y-%m-%dT%H:%M:%S").unwrap())
+                        .unwrap(),
+                ),
+            };
+            assert_eq!(parsed_due, task.due);
+        }
+    }
+
+    #[test]
+    fn csv_fields_are_only_quoted_when_needed() {
+        assert_eq!(escape_csv_field("plain"), "plain");
+        assert_eq!(escape_csv_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2030, 6, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    /// A table with a task overdue, one due soon, one due later, one without a due and a
+    /// description too long to fit
+    fn table() -> GetTaskResponse {
+        GetTaskResponse {
+            res: vec![
+                task("pay rent", "home", Some(now() - TimeDelta::hours(3))),
+                task("standup", "work", Some(now() + TimeDelta::minutes(20))),
+                GetTaskResponseDetail {
+                    note: Some("bring the slides".to_owned()),
+                    ..task("review", "work", Some(now() + TimeDelta::days(2)))
+                },
+                task(&"a very long description ".repeat(4), "", None),
+            ],
+            ..GetTaskResponse::default()
+        }
+    }
+
+    fn render(tasks: &GetTaskResponse) -> String {
+        let mut out = String::new();
+        tasks
+            .write_table(&mut out, now(), Zone::Named(chrono_tz::UTC))
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn the_table_is_aligned() {
+        let expected = "\
++ ------------------------------------------------------------ + ----- + ------ +
+| TASK                                                         | GROUP | DUE    |
++ ------------------------------------------------------------ + ----- + ------ +
+| pay rent                                                     | home  | 3h ago |
+| standup                                                      | work  | in 20m |
+| review                                                       | work  | in 2d  |
+|   note: bring the slides                                     |       |        |
+| a very long description a very long description a very long… |       |        |
++ ------------------------------------------------------------ + ----- + ------ +
+4 items (1 overdue)
+";
+
+        assert_eq!(strip_colors(&render(&table())), expected);
+    }
+
+    #[test]
+    fn the_table_with_absolute_dues_is_aligned() {
+        let tasks = GetTaskResponse {
+            res: table().res.into_iter().take(2).collect(),
+            absolute_dues: true,
+            ..GetTaskResponse::default()
+        };
+        let expected = "\
++ -------- + ----- + ------------------- +
+| TASK     | GROUP | DUE (UTC)           |
++ -------- + ----- + ------------------- +
+| pay rent | home  | 2030-06-01 09:00:00 |
+| standup  | work  | 2030-06-01 12:20:00 |
++ -------- + ----- + ------------------- +
+2 items (1 overdue)
+";
+
+        assert_eq!(strip_colors(&render(&tasks)), expected);
+    }
+
+    #[test]
+    fn a_table_without_dues_has_no_due_column() {
+        let tasks = GetTaskResponse {
+            res: vec![task("buy milk", "home", None)],
+            ..GetTaskResponse::default()
+        };
+        let expected = "\
++ -------- + ----- +
+| TASK     | GROUP |
++ -------- + ----- +
+| buy milk | home  |
++ -------- + ----- +
+1 item
+";
+
+        assert_eq!(strip_colors(&render(&tasks)), expected);
+    }
+
+    #[test]
+    fn relative_dues_keep_two_units() {
+        let due_in = short_relative_time;
+
+        // past
+        assert_eq!(due_in(-TimeDelta::minutes(5)), "5m ago");
+        assert_eq!(due_in(-TimeDelta::hours(26)), "1d 2h ago");
+        // near future
+        assert_eq!(due_in(TimeDelta::seconds(20)), "now");
+        assert_eq!(due_in(TimeDelta::seconds(90)), "in 2m");
+        assert_eq!(due_in(TimeDelta::minutes(90)), "in 1h 30m");
+        assert_eq!(due_in(TimeDelta::hours(2)), "in 2h");
+        assert_eq!(
+            due_in(TimeDelta::minutes(3 * 24 * 60 + 4 * 60 + 10)),
+            "in 3d 4h"
+        );
+        // far future
+        assert_eq!(due_in(TimeDelta::days(3)), "in 3d");
+        assert_eq!(due_in(TimeDelta::days(10) + TimeDelta::hours(5)), "in 10d");
+    }
+
+    #[test]
+    fn long_relative_times_keep_the_biggest_unit() {
+        assert_eq!(relative_time(TimeDelta::seconds(30)), "now");
+        assert_eq!(relative_time(-TimeDelta::minutes(1)), "1 minute ago");
+        assert_eq!(relative_time(TimeDelta::minutes(90)), "in 1 hour");
+        assert_eq!(relative_time(TimeDelta::days(40)), "in 40 days");
+    }
+
+    #[test]
+    fn the_rows_are_colored_by_due() {
+        let color = |due| due_color(&task("task", "", due), now());
+
+        assert_eq!(color(Some(now() - TimeDelta::minutes(1))), "\x1b[31m");
+        assert_eq!(color(Some(now() + TimeDelta::minutes(59))), "\x1b[33m");
+        assert_eq!(color(Some(now() + TimeDelta::hours(2))), "");
+        // a task without a due is never overdue
+        assert_eq!(color(None), "");
+    }
+}
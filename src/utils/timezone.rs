@@ -0,0 +1,118 @@
+/// # Timezone Module
+///
+/// This module handles the timezone the dues are shown and typed in.
+///
+/// The server stores dues as naive datetimes in UTC, the dues typed by the user are
+/// interpreted in the zone of the run (the `--due-timezone` flag, the `timezone` of the
+/// config or the system local zone) and converted to UTC before being sent, the dues
+/// received are converted back to it before being shown.
+/// A wall clock time skipped or repeated by a daylight saving change can't be converted
+/// and is refused.
+///
+/// ## Types
+///
+/// - `Zone`: Either the system local zone or a named IANA zone.
+///
+/// ## Functions
+///
+/// - `set_zone`: Sets the zone used for the whole run, done once at startup.
+/// - `zone`: Returns the zone set at startup, the local one if none was set.
+/// - `server_now`: Returns the current time as the server stores it, to compare with dues.
+use std::sync::OnceLock;
+
+use chrono::{Local, LocalResult, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::error::{Error, Result};
+
+static ZONE: OnceLock<Zone> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Zone {
+    #[default]
+    Local,
+    Named(Tz),
+}
+
+impl std::str::FromStr for Zone {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse::<Tz>()
+            .map(Zone::Named)
+            .map_err(|_| Error::InvalidTimezone)
+    }
+}
+
+impl std::fmt::Display for Zone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Zone::Local => write!(f, "local"),
+            Zone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+impl Zone {
+    /// The current wall clock time in this zone
+    pub fn now(&self) -> NaiveDateTime {
+        match self {
+            Zone::Local => Local::now().naive_local(),
+            Zone::Named(tz) => Local::now().with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// Converts a wall clock time of this zone to the UTC time the server stores, fails for
+    /// the times that don't exist (DST gaps) or happen twice (DST overlaps) in this zone
+    pub fn to_server(
+        &self,
+        datetime: NaiveDateTime,
+    ) -> std::result::Result<NaiveDateTime, &'static str> {
+        let converted = match self {
+            Zone::Local => utc_of(Local.from_local_datetime(&datetime)),
+            Zone::Named(tz) => utc_of(tz.from_local_datetime(&datetime)),
+        };
+        match converted {
+            LocalResult::Single(datetime) => Ok(datetime),
+            LocalResult::Ambiguous(_, _) => Err(
+                "The time happens twice in the timezone because of a daylight saving change, \
+                 pick another one",
+            ),
+            LocalResult::None => Err(
+                "The time does not exist in the timezone because of a daylight saving change, \
+                 pick another one",
+            ),
+        }
+    }
+
+    /// Converts a UTC time stored by the server to the wall clock time of this zone
+    pub fn from_server(&self, datetime: NaiveDateTime) -> NaiveDateTime {
+        let utc = Utc.from_utc_datetime(&datetime);
+        match self {
+            Zone::Local => utc.with_timezone(&Local).naive_local(),
+            Zone::Named(tz) => utc.with_timezone(tz).naive_local(),
+        }
+    }
+}
+
+/// Maps the result of a local time lookup to naive UTC times
+fn utc_of<Tz: TimeZone>(
+    converted: LocalResult<chrono::DateTime<Tz>>,
+) -> LocalResult<NaiveDateTime> {
+    converted.map(|datetime| datetime.naive_utc())
+}
+
+/// Sets the zone used for the whole run, calls after the first one are ignored
+pub fn set_zone(zone: Zone) {
+    let _ = ZONE.set(zone);
+}
+
+/// Returns the zone set at startup, the local one if none was set
+pub fn zone() -> Zone {
+    ZONE.get().copied().unwrap_or_default()
+}
+
+/// Returns the current time as the server stores it (naive UTC), the dues are compared to it
+pub fn server_now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
@@ -0,0 +1,149 @@
+/// # Wizard Module
+///
+/// This module asks the fields of a task one by one (`add --interactive`), for the users who
+/// find the flags of `add` confusing.
+///
+/// The description is required, the due is parsed as a `Due` and asked again until it is
+/// valid, then shown back as a date to be confirmed, and the group is optional. The answers
+/// are read from any reader and the questions written to any writer, so the wizard doesn't
+/// depend on the terminal it runs in.
+///
+/// ## Types
+///
+/// - `WizardTask`: The fields of the task given to the wizard.
+///
+/// ## Functions
+///
+/// - `add_wizard`: Asks the fields of a task.
+use std::io::{BufRead, Write};
+
+use crate::error::{Error, Result};
+use crate::parsers::Due;
+use crate::utils::Choice;
+
+pub struct WizardTask {
+    pub description: String,
+    pub due: Option<Due>,
+    pub group: Option<String>,
+}
+
+/// Asks the description, the due and the group of a task
+///
+/// # Args
+/// - input: where the answers are read from
+/// - out: where the questions are written to
+pub fn add_wizard(input: &mut impl BufRead, out: &mut impl Write) -> Result<WizardTask> {
+    let description = loop {
+        let description = ask(input, out, "description: ")?;
+        if !description.is_empty() {
+            break description;
+        }
+        writeln!(out, "The description is required").map_err(|_| Error::RsmFailed)?;
+    };
+
+    let due = loop {
+        let raw = ask(
+            input,
+            out,
+            "due (e.g. 18:30, tomorrow 09:00, +2h, empty for none): ",
+        )?;
+        if raw.is_empty() {
+            break None;
+        }
+
+        match raw.parse::<Due>() {
+            Ok(due) => {
+                let answer = ask(
+                    input,
+                    out,
+                    &format!("due {}, right? [Y/n]: ", due.describe()),
+                )?;
+                // an empty answer is a yes
+                if !matches!(Choice::from_input(&answer), Some(Choice::No)) {
+                    break Some(due);
+                }
+            }
+            Err(e) => writeln!(out, "{e}, try again").map_err(|_| Error::RsmFailed)?,
+        }
+    };
+
+    let group = ask(input, out, "group (empty for the default one): ")?;
+    let group = Some(group).filter(|group| !group.is_empty());
+
+    Ok(WizardTask {
+        description,
+        due,
+        group,
+    })
+}
+
+/// Writes a question and reads the trimmed answer, a closed input is an error instead of
+/// an endless prompt
+fn ask(input: &mut impl BufRead, out: &mut impl Write, question: &str) -> Result<String> {
+    write!(out, "{question}")
+        .and_then(|_| out.flush())
+        .map_err(|_| Error::RsmFailed)?;
+
+    let mut answer = String::new();
+    match input.read_line(&mut answer) {
+        Ok(0) => Err(Error::RsmFailed),
+        Ok(_) => Ok(answer.trim().to_owned()),
+        Err(e) => {
+            log::error!("Failed to read the answer: {e}");
+            Err(Error::RsmFailed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const DUE: &str = "2099-01-02 10:00";
+
+    fn run(answers: &str) -> (Result<WizardTask>, String) {
+        let mut out = Vec::new();
+        let task = add_wizard(&mut Cursor::new(answers), &mut out);
+        (task, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn a_wrong_due_is_asked_again() {
+        let (task, out) = run(&format!("buy milk\nsoonish\n{DUE}\n\nhome\n"));
+
+        let task = task.unwrap();
+        assert_eq!(task.description, "buy milk");
+        assert_eq!(task.due.unwrap().0, DUE.parse::<Due>().unwrap().0);
+        assert_eq!(task.group.as_deref(), Some("home"));
+        assert_eq!(out.matches("due (e.g.").count(), 2);
+        assert!(out.contains(", try again"));
+    }
+
+    #[test]
+    fn a_refused_due_is_asked_again() {
+        let (task, out) = run(&format!("buy milk\n+2h\nn\n{DUE}\ny\n\n"));
+
+        let task = task.unwrap();
+        assert_eq!(task.due.unwrap().0, DUE.parse::<Due>().unwrap().0);
+        assert_eq!(task.group, None);
+        assert_eq!(out.matches("right? [Y/n]").count(), 2);
+    }
+
+    #[test]
+    fn an_empty_description_is_asked_again() {
+        let (task, out) = run("\nbuy milk\n\n\n");
+
+        let task = task.unwrap();
+        assert_eq!(task.description, "buy milk");
+        assert!(task.due.is_none());
+        assert!(out.contains("The description is required"));
+    }
+
+    #[test]
+    fn a_closed_input_is_an_error() {
+        let (task, _) = run("buy milk\nsoonish\n");
+
+        assert!(matches!(task, Err(Error::RsmFailed)));
+    }
+}
@@ -0,0 +1,545 @@
+//! Checks the requests the `Api` sends and how it reads the answers, against a mock backend.
+mod common;
+
+use std::collections::HashMap;
+
+use common::{MockResponse, MockServer, TOKEN};
+use rsm::{Error, ErrorResponse, FormattedResponse, SuccessfulResponse, TaskListing};
+
+const NO_AUTH: &str = r#"{"error":{"req_uuid":"1234-abcd","type":"NO_AUTH"}}"#;
+
+fn success(response: &dyn FormattedResponse) -> &SuccessfulResponse {
+    response
+        .as_any()
+        .downcast_ref::<SuccessfulResponse>()
+        .expect("expected a successful response")
+}
+
+fn api_error(response: &dyn FormattedResponse) -> &ErrorResponse {
+    response
+        .as_any()
+        .downcast_ref::<ErrorResponse>()
+        .expect("expected an error response")
+}
+
+// -- add_task region
+#[test]
+fn add_task_posts_the_task() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"added"}"#)]);
+    let body = HashMap::from([("description", "buy  milk"), ("group", "home")]);
+
+    let response = server.api().add_task("todo".to_owned(), body).unwrap();
+
+    assert_eq!(success(response.as_ref()).res, "added");
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    let request = &requests[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "/todo");
+    assert_eq!(request.header("cookie"), Some(TOKEN));
+    assert_eq!(request.header("content-type"), Some("application/json"));
+    let payload = request.json();
+    // the inner whitespace is collapsed by default
+    assert_eq!(payload["description"], "buy milk");
+    assert_eq!(payload["group"], "home");
+    assert!(payload.get("due").is_none());
+}
+
+#[test]
+fn add_task_to_a_custom_table_goes_under_user() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"added"}"#)]);
+    let body = HashMap::from([("description", "review")]);
+
+    server
+        .api()
+        .add_task("work stuff".to_owned(), body)
+        .unwrap();
+
+    assert_eq!(server.requests()[0].path, "/user/work%20stuff");
+}
+
+#[test]
+fn add_task_reads_the_error_response() {
+    let server = MockServer::start(vec![MockResponse::new(401, NO_AUTH)]);
+    let body = HashMap::from([("description", "buy milk")]);
+
+    let response = server.api().add_task("todo".to_owned(), body).unwrap();
+
+    assert_eq!(api_error(response.as_ref()).error.req_uuid, "1234-abcd");
+}
+// -- end add_task region
+
+// -- get_tasks region
+#[test]
+fn get_tasks_lists_the_tasks_of_a_table() {
+    let server = MockServer::start(vec![MockResponse::new(
+        200,
+        r#"{"res":[{"description":"buy milk","group":"home","due":"2024-06-02T18:30:00"}]}"#,
+    )]);
+
+    let listing = server
+        .api()
+        .get_tasks(Some("reminder"), HashMap::from([("group", "home")]))
+        .unwrap();
+
+    let TaskListing::Tasks(tasks) = listing else {
+        panic!("expected the tasks");
+    };
+    assert_eq!(tasks.res.len(), 1);
+    assert_eq!(tasks.res[0].description, "buy milk");
+    assert!(tasks.res[0].due.is_some());
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "GET");
+    assert_eq!(request.path, "/reminder?group=home");
+    assert_eq!(request.header("cookie"), Some(TOKEN));
+}
+
+#[test]
+fn get_tasks_without_a_table_lists_the_tables() {
+    let server = MockServer::start(vec![MockResponse::new(
+        200,
+        r#"{"res":[{"name":"todo","has_due":false},{"name":"work","has_due":true}]}"#,
+    )]);
+
+    let listing = server.api().get_tasks(None, HashMap::new()).unwrap();
+
+    let TaskListing::Tables(tables) = listing else {
+        panic!("expected the tables");
+    };
+    let names: Vec<_> = tables.res.iter().map(|table| table.name.as_str()).collect();
+    assert_eq!(names, ["todo", "work"]);
+    assert_eq!(server.requests()[0].path, "/list");
+}
+
+#[test]
+fn get_tasks_reads_the_error_response() {
+    let server = MockServer::start(vec![MockResponse::new(401, NO_AUTH)]);
+
+    let listing = server
+        .api()
+        .get_tasks(Some("todo"), HashMap::new())
+        .unwrap();
+
+    assert!(matches!(listing, TaskListing::ApiError(_)));
+}
+#[test]
+fn get_tasks_with_a_task_named_error_is_a_success() {
+    let server = MockServer::start(vec![MockResponse::new(
+        200,
+        r#"{"res":[{"description":"error in prod","group":"work"}]}"#,
+    )]);
+
+    let listing = server
+        .api()
+        .get_tasks(Some("todo"), HashMap::new())
+        .unwrap();
+
+    let TaskListing::Tasks(tasks) = listing else {
+        panic!("expected the tasks");
+    };
+    assert_eq!(tasks.res[0].description, "error in prod");
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_get_tasks_lists_the_tasks_of_a_table() {
+    let server = MockServer::start(vec![MockResponse::new(
+        200,
+        r#"{"res":[{"description":"buy milk","group":"home"}]}"#,
+    )]);
+
+    let listing = server
+        .async_api()
+        .get_tasks(Some("todo"), HashMap::from([("group", "home")]))
+        .await
+        .unwrap();
+
+    let TaskListing::Tasks(tasks) = listing else {
+        panic!("expected the tasks");
+    };
+    assert_eq!(tasks.res[0].description, "buy milk");
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "GET");
+    assert_eq!(request.path, "/todo?group=home");
+    assert_eq!(request.header("cookie"), Some(TOKEN));
+}
+
+#[test]
+fn get_tasks_is_retried_until_the_server_answers() {
+    let server = MockServer::start(vec![
+        MockResponse::new(500, ""),
+        MockResponse::new(503, ""),
+        MockResponse::new(
+            200,
+            r#"{"res":[{"description":"buy milk","group":"home"}]}"#,
+        ),
+    ]);
+
+    let listing = server
+        .retrying_api(3)
+        .get_tasks(Some("todo"), HashMap::new())
+        .unwrap();
+
+    let TaskListing::Tasks(tasks) = listing else {
+        panic!("expected the tasks");
+    };
+    assert_eq!(tasks.res[0].description, "buy milk");
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[test]
+fn the_requests_identify_the_client() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"res":[]}"#),
+        MockResponse::new(200, r#"{"res":"added"}"#),
+    ]);
+    let api = server.api();
+
+    api.get_tasks(Some("todo"), HashMap::new()).unwrap();
+    api.add_task(
+        "todo".to_owned(),
+        HashMap::from([("description", "buy milk")]),
+    )
+    .unwrap();
+
+    let expected = format!("cli_client/{}", env!("CARGO_PKG_VERSION"));
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    for request in requests {
+        assert_eq!(request.header("user-agent"), Some(expected.as_str()));
+    }
+}
+
+#[test]
+fn get_tasks_encodes_the_table_name() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":[]}"#)]);
+
+    server
+        .api()
+        .get_tasks(Some("my list"), HashMap::new())
+        .unwrap();
+
+    assert_eq!(server.requests()[0].path, "/user/my%20list");
+}
+
+// -- end get_tasks region
+
+// -- clear_table region
+#[test]
+fn clear_table_deletes_the_tasks() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"cleared"}"#)]);
+
+    let response = server.api().clear_table("todo".to_owned()).unwrap();
+
+    assert_eq!(success(response.as_ref()).res, "cleared");
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "DELETE");
+    assert_eq!(request.path, "/todo/clear");
+    assert_eq!(request.header("cookie"), Some(TOKEN));
+}
+
+#[test]
+fn clear_table_of_a_missing_table_is_not_found() {
+    let server = MockServer::start(vec![MockResponse::new(404, "")]);
+
+    let result = server.api().clear_table("gone".to_owned());
+
+    assert!(matches!(result, Err(Error::NotFound)));
+    assert_eq!(server.requests()[0].path, "/user/gone/clear");
+}
+#[test]
+fn clear_table_encodes_the_table_name() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"cleared"}"#)]);
+
+    server.api().clear_table("my list/2".to_owned()).unwrap();
+
+    assert_eq!(server.requests()[0].path, "/user/my%20list%2F2/clear");
+}
+// -- end clear_table region
+
+// -- remove_tasks region
+#[test]
+fn remove_tasks_goes_on_past_a_task_that_fails() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"res":"removed"}"#),
+        MockResponse::new(404, ""),
+        MockResponse::new(200, r#"{"res":"removed"}"#),
+    ]);
+    let descs = ["buy milk", "missing", "call mom"].map(String::from);
+
+    let report = server.api().remove_tasks("todo", &descs);
+
+    assert_eq!(report.succeeded(), 2);
+    assert_eq!(report.failed(), 1);
+    let outcomes: Vec<_> = report
+        .items()
+        .iter()
+        .map(|item| (item.item.as_str(), item.ok))
+        .collect();
+    assert_eq!(
+        outcomes,
+        [("buy milk", true), ("missing", false), ("call mom", true)]
+    );
+    let paths: Vec<_> = server
+        .requests()
+        .into_iter()
+        .map(|request| (request.method, request.path))
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            ("DELETE".to_owned(), "/todo/buy%20milk".to_owned()),
+            ("DELETE".to_owned(), "/todo/missing".to_owned()),
+            ("DELETE".to_owned(), "/todo/call%20mom".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn remove_tasks_counts_an_error_response_as_a_failure() {
+    let server = MockServer::start(vec![
+        MockResponse::new(401, NO_AUTH),
+        MockResponse::new(200, r#"{"res":"removed"}"#),
+    ]);
+    let descs = ["first", "second"].map(String::from);
+
+    let report = server.api().remove_tasks("todo", &descs);
+
+    assert!(!report.items()[0].ok);
+    assert!(report.items()[1].ok);
+}
+// -- end remove_tasks region
+
+// -- move_task region
+#[test]
+fn move_task_adds_to_the_target_then_removes_from_the_source() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"res":"added"}"#),
+        MockResponse::new(200, r#"{"res":"removed"}"#),
+    ]);
+    let body = HashMap::from([("description", "buy milk"), ("group", "home")]);
+
+    let response = server
+        .api()
+        .move_task("todo".to_owned(), "work".to_owned(), body)
+        .unwrap();
+
+    assert_eq!(
+        success(response.as_ref()).res,
+        "Moved 'buy milk' from 'todo' to 'work'"
+    );
+    let requests: Vec<_> = server
+        .requests()
+        .into_iter()
+        .map(|request| (request.method, request.path))
+        .collect();
+    assert_eq!(
+        requests,
+        [
+            ("POST".to_owned(), "/user/work".to_owned()),
+            ("DELETE".to_owned(), "/todo/buy%20milk".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn move_task_removes_the_copy_when_the_source_fails() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"res":"added"}"#),
+        MockResponse::new(500, ""),
+        MockResponse::new(200, r#"{"res":"removed"}"#),
+    ]);
+    let body = HashMap::from([("description", "buy milk")]);
+
+    let result = server
+        .api()
+        .move_task("todo".to_owned(), "work".to_owned(), body);
+
+    assert!(matches!(result, Err(Error::ServerError(500))));
+    let requests: Vec<_> = server
+        .requests()
+        .into_iter()
+        .map(|request| (request.method, request.path))
+        .collect();
+    assert_eq!(
+        requests,
+        [
+            ("POST".to_owned(), "/user/work".to_owned()),
+            ("DELETE".to_owned(), "/todo/buy%20milk".to_owned()),
+            ("DELETE".to_owned(), "/user/work/buy%20milk".to_owned()),
+        ]
+    );
+}
+// -- end move_task region
+
+// -- rename_table region
+#[test]
+fn rename_table_refuses_the_builtin_tables() {
+    let server = MockServer::start(vec![]);
+
+    for builtin in ["reminder", " todo "] {
+        let result = server.api().rename_table(builtin, "chores");
+        assert!(matches!(result, Err(Error::BuiltinTable)));
+    }
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn rename_table_refuses_a_builtin_name() {
+    let server = MockServer::start(vec![]);
+
+    let result = server.api().rename_table("chores", "todo");
+
+    assert!(matches!(result, Err(Error::InvalidTableName)));
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn rename_table_patches_the_name() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"renamed"}"#)]);
+
+    let response = server.api().rename_table("chores", " house ").unwrap();
+
+    assert_eq!(success(response.as_ref()).res, "renamed");
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "PATCH");
+    assert_eq!(request.path, "/chores");
+    assert_eq!(request.json()["name"], "house");
+}
+// -- end rename_table region
+
+// -- status errors region
+fn add_task_answered_with(status: u16, body: &str) -> rsm::Result<Box<dyn FormattedResponse>> {
+    let server = MockServer::start(vec![MockResponse::new(status, body)]);
+    server.api().add_task(
+        "todo".to_owned(),
+        HashMap::from([("description", "buy milk")]),
+    )
+}
+
+#[test]
+fn a_401_without_a_body_is_unauthorized() {
+    let err = add_task_answered_with(401, "").err().unwrap();
+
+    assert!(matches!(err, Error::Unauthorized));
+    assert_eq!(err.exit_code(), 3);
+    assert!(err.to_string().contains("401"));
+}
+
+#[test]
+fn a_404_without_a_body_is_not_found() {
+    let err = add_task_answered_with(404, "").err().unwrap();
+
+    assert!(matches!(err, Error::NotFound));
+    assert_eq!(err.exit_code(), 2);
+    assert!(err.to_string().contains("404"));
+}
+
+#[test]
+fn a_500_without_a_body_is_a_server_error() {
+    let err = add_task_answered_with(500, "").err().unwrap();
+
+    assert!(matches!(err, Error::ServerError(500)));
+    assert_eq!(err.exit_code(), 4);
+    assert!(err.to_string().contains("500"));
+}
+
+#[test]
+fn a_400_keeps_the_detail_of_the_server() {
+    let err = add_task_answered_with(400, "due is malformed")
+        .err()
+        .unwrap();
+
+    assert!(matches!(&err, Error::BadRequest(detail) if detail == "due is malformed"));
+    assert_eq!(err.exit_code(), 2);
+}
+
+#[test]
+fn an_error_body_gets_the_hint_of_its_type() {
+    let cases = [
+        (
+            401,
+            NO_AUTH,
+            "The session expired or is missing, log in again",
+        ),
+        (
+            404,
+            r#"{"error":{"req_uuid":"1234-abcd","type":"USER_NOT_FOUND"}}"#,
+            "No account with this username exists",
+        ),
+        (
+            500,
+            r#"{"error":{"req_uuid":"1234-abcd","type":"SERVICE_ERROR"}}"#,
+            "The server failed, try again later",
+        ),
+    ];
+
+    for (status, body, hint) in cases {
+        let response = add_task_answered_with(status, body).unwrap();
+
+        let err = api_error(response.as_ref());
+        assert_eq!(err.error.error_type.hint(), hint);
+        assert!(err.to_string().contains(hint), "{status} lacks its hint");
+    }
+}
+// -- end status errors region
+
+// -- auth region
+#[test]
+fn signup_sends_the_trimmed_credentials() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"the-key"}"#)]);
+
+    let response = server
+        .api_without_token()
+        .post_signup(" user ", "secret\n")
+        .unwrap();
+
+    assert_eq!(success(response.as_ref()).res, "the-key");
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "/signup");
+    assert_eq!(request.header("content-type"), Some("application/json"));
+    assert_eq!(request.json()["username"], "user");
+    assert_eq!(request.json()["password"], "secret");
+}
+
+#[test]
+fn login_returns_the_session_set_by_the_server() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"logged in"}"#)
+        .with_header("Set-Cookie", "id=abc; Path=/; HttpOnly; Max-Age=3600")]);
+
+    let (response, session) = server.api_without_token().post_login("the-key").unwrap();
+
+    assert_eq!(success(response.as_ref()).res, "logged in");
+    let session = session.expect("expected a session");
+    assert_eq!(session.name, "id");
+    assert_eq!(session.value, "abc");
+    assert!(session.expires.is_some());
+    let request = &server.requests()[0];
+    assert_eq!(request.path, "/login");
+    assert_eq!(request.json()["key"], "the-key");
+}
+
+#[test]
+fn login_with_a_wrong_key_has_no_session() {
+    let server = MockServer::start(vec![MockResponse::new(
+        401,
+        r#"{"error":{"req_uuid":"1234-abcd","type":"LOGIN_FAIL"}}"#,
+    )]);
+
+    let (response, session) = server.api_without_token().post_login("wrong").unwrap();
+
+    api_error(response.as_ref());
+    assert!(session.is_none());
+}
+
+#[test]
+fn login_with_an_expired_session_is_refused() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"logged in"}"#)
+        .with_header("Set-Cookie", "id=abc; Path=/; Max-Age=0")]);
+
+    let result = server.api_without_token().post_login("the-key");
+
+    assert!(matches!(result, Err(Error::NoAuth)));
+}
+// -- end auth region
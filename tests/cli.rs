@@ -0,0 +1,627 @@
+//! Checks the output of the binary end to end, against a mock backend. The token comes from
+//! the environment so that no config file is read or written.
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use common::{MockResponse, MockServer};
+
+/// Runs the binary with `args` against the server
+fn rsm(server: &MockServer, args: &[&str]) -> Output {
+    rsm_command(server, args)
+        .env("CLI_CLIENT_TOKEN", "id=test-token")
+        .output()
+        .expect("failed to run rsm")
+}
+
+/// The binary with `args` against the server, without a token
+fn rsm_command(server: &MockServer, args: &[&str]) -> Command {
+    let temp_dir = std::env::temp_dir();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rsm"));
+    command
+        .args(args)
+        .env_remove("CLI_CLIENT_TOKEN")
+        .env_remove("RSM_TOKEN")
+        .env("RSM_BACKEND", server.url())
+        .env(
+            "LOG",
+            temp_dir.join(format!("rsm-cli-{}.log", std::process::id())),
+        )
+        .env("NO_COLOR", "1");
+    command
+}
+
+#[test]
+fn add_prints_the_answer_of_the_server() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"added"}"#)]);
+
+    let output = rsm(&server, &["add", "todo", "-t", "buy milk"]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("added"));
+}
+
+#[test]
+fn quiet_add_prints_nothing_on_stdout() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"added"}"#)]);
+
+    let output = rsm(&server, &["add", "todo", "-t", "buy milk", "-q"]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(server.requests()[0].method, "POST");
+}
+
+#[test]
+fn quiet_add_refused_by_the_server_fails_on_stderr() {
+    let server = MockServer::start(vec![MockResponse::new(
+        401,
+        r#"{"error":{"req_uuid":"1234-abcd","type":"NO_AUTH"}}"#,
+    )]);
+
+    let output = rsm(&server, &["add", "todo", "-t", "buy milk", "--quiet"]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1234-abcd"));
+}
+
+#[test]
+fn a_missing_table_is_hinted_on_stderr() {
+    let server = MockServer::start(vec![]);
+
+    let output = rsm(&server, &["add", "-t", "buy milk"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("config set default_table"));
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn an_ambiguous_name_lists_the_matches_on_stderr() {
+    let server = MockServer::start(vec![MockResponse::new(
+        200,
+        r#"{"res":[{"description":"buy milk","group":"home"},{"description":"buy oat milk","group":"home"}]}"#,
+    )]);
+
+    let output = rsm(&server, &["remove", "todo", "--name", "milk", "--yes"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("- buy milk"));
+    assert!(stderr.contains("- buy oat milk"));
+    // nothing gets removed
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn removing_by_name_without_a_terminal_does_not_ask() {
+    let server = MockServer::start(vec![
+        MockResponse::new(
+            200,
+            r#"{"res":[{"description":"buy oat milk","group":"home"}]}"#,
+        ),
+        MockResponse::new(200, r#"{"res":"removed"}"#),
+    ]);
+
+    let output = rsm_command(&server, &["remove", "todo", "--name", "milk"])
+        .env("CLI_CLIENT_TOKEN", "id=test-token")
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run rsm");
+
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Remove 'buy oat milk'"));
+    let requests = server.requests();
+    assert_eq!(requests[1].method, "DELETE");
+    assert_eq!(requests[1].path, "/todo/buy%20oat%20milk");
+}
+
+#[test]
+fn whoami_without_a_session_fails_on_stderr() {
+    let server = MockServer::start(vec![]);
+    let config_dir = std::env::temp_dir().join(format!("rsm-cli-whoami-{}", std::process::id()));
+    std::fs::create_dir_all(&config_dir).unwrap();
+
+    let output = rsm_command(&server, &["whoami"])
+        .env("CLI_CLIENT_CONFIG", config_dir.join("rsm-conf.json"))
+        .output()
+        .expect("failed to run rsm");
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Not logged in"));
+    std::fs::remove_dir_all(config_dir).unwrap();
+}
+
+#[test]
+fn list_of_an_empty_group_says_so() {
+    let server = MockServer::start(vec![MockResponse::new(
+        200,
+        r#"{"res":[{"description":"buy milk","group":"home"},{"description":"standup","group":"work"}]}"#,
+    )]);
+
+    // the server ignores the group, the client filters it
+    let output = rsm(&server, &["list", "todo", "--group", "gym"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "No tasks in group gym\n"
+    );
+    assert_eq!(server.requests()[0].path, "/todo?group=gym");
+}
+
+#[test]
+fn completions_are_generated_for_each_shell() {
+    let server = MockServer::start(vec![]);
+
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        let output = rsm(&server, &["completions", shell]);
+
+        assert!(output.status.success(), "{shell} failed");
+        let script = String::from_utf8_lossy(&output.stdout);
+        assert!(script.contains("rsm"), "{shell} has no script");
+    }
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn completions_of_an_unknown_shell_are_refused() {
+    let server = MockServer::start(vec![]);
+
+    let output = rsm(&server, &["completions", "tcsh"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+}
+
+/// Writes a config file with the given account in its own directory, returns its path
+fn config_file(name: &str, profile: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("rsm-cli-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("rsm-conf.json");
+    std::fs::write(
+        &path,
+        format!(r#"{{"first_run": false, "profiles": {{"default": {profile}}}}}"#),
+    )
+    .unwrap();
+    path
+}
+
+const EXPIRED_TOKEN: &str = "id=old; Path=/; HttpOnly; Expires=Wed, 01 Jan 2020 00:00:00 GMT";
+
+#[test]
+fn an_expired_session_is_refreshed_with_the_saved_key() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"res":"logged in"}"#)
+            .with_header("Set-Cookie", "id=new; Path=/; HttpOnly; Max-Age=3600"),
+        MockResponse::new(200, r#"{"res":[]}"#),
+    ]);
+    let config = config_file(
+        "refresh",
+        &format!(r#"{{"key": "the-key", "token": "{EXPIRED_TOKEN}"}}"#),
+    );
+
+    let output = rsm_command(&server, &["list", "todo"])
+        .env("CLI_CLIENT_CONFIG", &config)
+        .output()
+        .expect("failed to run rsm");
+
+    assert!(output.status.success());
+    let requests = server.requests();
+    assert_eq!(requests[0].path, "/login");
+    assert_eq!(requests[0].json()["key"], "the-key");
+    assert_eq!(requests[1].path, "/todo");
+    assert!(requests[1].header("cookie").unwrap().contains("id=new"));
+    // the new session is kept for the next runs
+    let stored = std::fs::read_to_string(&config).unwrap();
+    assert!(stored.contains("id=new"));
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn an_expired_session_without_a_saved_key_needs_a_login() {
+    let server = MockServer::start(vec![]);
+    let config = config_file("no-key", &format!(r#"{{"token": "{EXPIRED_TOKEN}"}}"#));
+
+    let output = rsm_command(&server, &["list", "todo"])
+        .env("CLI_CLIENT_CONFIG", &config)
+        .output()
+        .expect("failed to run rsm");
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(server.requests().is_empty());
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn search_across_the_tables_names_the_owning_table() {
+    let server = MockServer::start(vec![
+        MockResponse::new(
+            200,
+            r#"{"res":[{"name":"todo","has_due":false},{"name":"work","has_due":false}]}"#,
+        ),
+        MockResponse::new(
+            200,
+            r#"{"res":[{"description":"buy milk","group":"home"}]}"#,
+        ),
+        MockResponse::new(
+            200,
+            r#"{"res":[{"description":"Migrate the DB","group":"ops"},{"description":"standup","group":"ops"}]}"#,
+        ),
+    ]);
+
+    let output = rsm(&server, &["search", "mIgRaTe", "--json"]);
+
+    assert!(output.status.success());
+    let found: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(found.as_array().unwrap().len(), 1);
+    assert_eq!(found[0]["table"], "work");
+    assert_eq!(found[0]["tasks"][0]["description"], "Migrate the DB");
+    assert_eq!(found[0]["tasks"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn search_without_a_match_says_so() {
+    let server = MockServer::start(vec![MockResponse::new(
+        200,
+        r#"{"res":[{"description":"buy milk","group":"home"}]}"#,
+    )]);
+
+    let output = rsm(&server, &["search", "eggs", "--table", "todo"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "No tasks match 'eggs'\n"
+    );
+}
+
+#[test]
+fn a_batch_goes_on_past_a_malformed_line() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"res":"added"}"#),
+        MockResponse::new(200, r#"{"res":"added"}"#),
+    ]);
+    let lines = "buy milk | 2031-01-01 10:00 | home\nbroken | 2031-13-01 10:00 |\n\ncall mom\n";
+
+    let mut child = rsm_command(&server, &["add", "todo", "--from", "-"])
+        .env("CLI_CLIENT_TOKEN", "id=test-token")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run rsm");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(lines.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    // a batch succeeds even when some lines fail
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("failed 'line 2': Invalid date"));
+    assert!(stdout.ends_with("2 succeeded, 1 failed ('line 2')\n"));
+    let added: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|request| request.json()["description"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(added, ["buy milk", "call mom"]);
+}
+
+#[test]
+fn undo_adds_back_a_removed_task_with_its_fields() {
+    let server = MockServer::start(vec![
+        MockResponse::new(
+            200,
+            r#"{"res":[{"description":"buy milk","group":"home","due":"2031-01-01T10:00:00","note":"semi-skimmed"}]}"#,
+        ),
+        MockResponse::new(200, r#"{"res":"removed"}"#),
+        MockResponse::new(200, r#"{"res":"added"}"#),
+    ]);
+    // the operations are logged next to the config, so it can't come from the environment
+    let config = config_file("undo", r#"{"token": "id=test-token"}"#);
+    let run = |args: &[&str]| {
+        rsm_command(&server, args)
+            .env("CLI_CLIENT_CONFIG", &config)
+            .output()
+            .expect("failed to run rsm")
+    };
+
+    assert!(run(&["remove", "todo", "buy milk"]).status.success());
+    let undo = run(&["undo"]);
+
+    assert!(undo.status.success());
+    assert!(String::from_utf8_lossy(&undo.stdout).contains("Undoing: remove 'buy milk' from todo"));
+    let requests = server.requests();
+    let methods: Vec<_> = requests
+        .iter()
+        .map(|request| (request.method.as_str(), request.path.as_str()))
+        .collect();
+    assert_eq!(
+        methods,
+        [
+            ("GET", "/todo"),
+            ("DELETE", "/todo/buy%20milk"),
+            ("POST", "/todo")
+        ]
+    );
+    let added = requests[2].json();
+    assert_eq!(added["description"], "buy milk");
+    assert_eq!(added["group"], "home");
+    assert_eq!(added["due"], "2031-01-01T10:00:00");
+    assert_eq!(added["note"], "semi-skimmed");
+
+    // the undone operation is gone
+    assert_eq!(run(&["undo"]).status.code(), Some(1));
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn an_undo_refused_by_the_server_can_be_retried() {
+    let server = MockServer::start(vec![
+        MockResponse::new(
+            200,
+            r#"{"res":[{"description":"buy milk","group":"home"}]}"#,
+        ),
+        MockResponse::new(200, r#"{"res":"removed"}"#),
+        MockResponse::new(
+            500,
+            r#"{"error":{"req_uuid":"1234-abcd","type":"SERVER_ERROR"}}"#,
+        ),
+        MockResponse::new(200, r#"{"res":"added"}"#),
+    ]);
+    let config = config_file("undo-retry", r#"{"token": "id=test-token"}"#);
+    let run = |args: &[&str]| {
+        rsm_command(&server, args)
+            .env("CLI_CLIENT_CONFIG", &config)
+            .output()
+            .expect("failed to run rsm")
+    };
+
+    assert!(run(&["remove", "todo", "buy milk"]).status.success());
+    let failed = run(&["undo"]);
+    let retried = run(&["undo"]);
+
+    assert!(!failed.status.success());
+    assert!(retried.status.success());
+    let requests = server.requests();
+    assert_eq!(requests.len(), 4);
+    assert_eq!(requests[3].method, "POST");
+    assert_eq!(requests[3].json()["description"], "buy milk");
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn dry_run_prints_the_request_with_the_token_redacted() {
+    let server = MockServer::start(vec![]);
+
+    let output = rsm(&server, &["add", "todo", "-t", "buy milk", "--dry-run"]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("POST {}/todo", server.url())));
+    assert!(stdout.contains("cookie: ***"));
+    assert!(stdout.contains(r#""description":"buy milk""#));
+    assert!(!stdout.contains("test-token"));
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn a_config_with_an_unknown_frontend_is_invalid() {
+    let server = MockServer::start(vec![]);
+    let config = config_file("frontend", r#"{"key": "the-key"}"#);
+    let contents =
+        std::fs::read_to_string(&config)
+            .unwrap()
+            .replacen('{', r#"{"frontend": "gui", "#, 1);
+    std::fs::write(&config, &contents).unwrap();
+
+    let output = rsm_command(&server, &["list", "todo"])
+        .env("CLI_CLIENT_CONFIG", &config)
+        .output()
+        .expect("failed to run rsm");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Error: The config file is invalid\n"));
+    assert!(server.requests().is_empty());
+    // the file is left for the user to fix
+    assert_eq!(std::fs::read_to_string(&config).unwrap(), contents);
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn a_failed_request_ends_with_the_request_id() {
+    let server = MockServer::start(vec![MockResponse::new(
+        401,
+        r#"{"error":{"req_uuid":"1234-abcd","type":"NO_AUTH"}}"#,
+    )]);
+
+    let output = rsm(&server, &["add", "todo", "-t", "buy milk"]);
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr.lines().last(),
+        Some("(server request id: 1234-abcd)")
+    );
+}
+
+#[test]
+fn the_request_id_of_a_success_is_only_printed_when_asked() {
+    let answer = r#"{"res":"added","req_uuid":"5678-efgh"}"#;
+    let server = MockServer::start(vec![
+        MockResponse::new(200, answer),
+        MockResponse::new(200, answer),
+    ]);
+
+    let silent = rsm(&server, &["add", "todo", "-t", "buy milk"]);
+    let asked = rsm(
+        &server,
+        &["add", "todo", "-t", "buy milk", "--print-request-id"],
+    );
+
+    assert!(silent.status.success());
+    assert!(!String::from_utf8_lossy(&silent.stderr).contains("request id"));
+    assert!(asked.status.success());
+    assert!(String::from_utf8_lossy(&asked.stderr).contains("(server request id: 5678-efgh)"));
+}
+
+#[test]
+fn a_due_on_a_table_without_dues_is_refused_before_sending() {
+    let server = MockServer::start(vec![MockResponse::new(
+        200,
+        r#"{"res":[{"name":"todo","has_due":false},{"name":"reminder","has_due":true}]}"#,
+    )]);
+
+    let output = rsm(
+        &server,
+        &["add", "todo", "-t", "buy milk", "--due", "tomorrow 09:00"],
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("The table doesn't support dues"));
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "GET");
+    assert_eq!(requests[0].path, "/list");
+}
+
+/// Writes the table cache next to `config`, with `todo` as a table without dues
+fn table_cache(config: &std::path::Path, fetched_at: DateTime<Utc>) -> std::path::PathBuf {
+    let path = config.with_file_name("tables.json");
+    std::fs::write(
+        &path,
+        format!(
+            r#"{{"tables": [{{"name": "todo", "has_due": false}}], "fetched_at": "{}"}}"#,
+            fetched_at.to_rfc3339()
+        ),
+    )
+    .unwrap();
+    path
+}
+
+#[test]
+fn the_cached_tables_are_used_until_they_are_stale() {
+    let fresh_server = MockServer::start(vec![]);
+    let stale_server = MockServer::start(vec![MockResponse::new(
+        200,
+        r#"{"res":[{"name":"todo","has_due":false}]}"#,
+    )]);
+    let config = config_file("table-cache", r#"{"key": "the-key", "token": "id=abc"}"#);
+    let add = ["add", "todo", "-t", "buy milk", "--due", "tomorrow 09:00"];
+
+    table_cache(&config, Utc::now());
+    let fresh = rsm_command(&fresh_server, &add)
+        .env("CLI_CLIENT_CONFIG", &config)
+        .output()
+        .expect("failed to run rsm");
+    table_cache(&config, Utc::now() - Duration::hours(2));
+    let stale = rsm_command(&stale_server, &add)
+        .env("CLI_CLIENT_CONFIG", &config)
+        .output()
+        .expect("failed to run rsm");
+
+    assert_eq!(fresh.status.code(), Some(2));
+    assert!(fresh_server.requests().is_empty());
+    assert_eq!(stale.status.code(), Some(2));
+    assert_eq!(stale_server.requests()[0].path, "/list");
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn a_due_on_a_missing_table_is_not_found_after_a_single_fetch() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"res":[{"name":"todo","has_due":false}]}"#),
+        MockResponse::new(200, r#"{"res":[{"name":"todo","has_due":false}]}"#),
+    ]);
+    let config = config_file(
+        "table-cache-missing",
+        r#"{"key": "the-key", "token": "id=abc"}"#,
+    );
+    table_cache(&config, Utc::now() - Duration::hours(2));
+
+    let output = rsm_command(
+        &server,
+        &["add", "work", "-t", "buy milk", "--due", "tomorrow 09:00"],
+    )
+    .env("CLI_CLIENT_CONFIG", &config)
+    .output()
+    .expect("failed to run rsm");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No table with this name"));
+    assert_eq!(server.requests().len(), 1);
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn creating_a_table_drops_the_cached_tables() {
+    let server = MockServer::start(vec![MockResponse::new(200, r#"{"res":"created"}"#)]);
+    let config = config_file(
+        "table-cache-create",
+        r#"{"key": "the-key", "token": "id=abc"}"#,
+    );
+    let cache = table_cache(&config, Utc::now());
+
+    let output = rsm_command(&server, &["create", "work"])
+        .env("CLI_CLIENT_CONFIG", &config)
+        .output()
+        .expect("failed to run rsm");
+
+    assert!(output.status.success());
+    assert_eq!(server.requests()[0].method, "POST");
+    assert!(!cache.exists());
+    std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn an_error_is_reported_with_its_readable_text() {
+    let server = MockServer::start(vec![MockResponse::new(500, "")]);
+
+    let output = rsm(&server, &["add", "todo", "-t", "buy milk"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error: The server failed to answer (500)"));
+    assert!(!stderr.contains("ServerError"));
+}
+
+#[test]
+fn new_key_in_json_mode_prints_no_banner() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200, r#"{"res":"the new key is \"new-key\""}"#),
+        MockResponse::new(200, r#"{"res":"logged in"}"#)
+            .with_header("Set-Cookie", "id=new; Path=/; HttpOnly"),
+    ]);
+
+    let mut child = rsm_command(&server, &["new-key", "-u", "me", "--json"])
+        .env("CLI_CLIENT_TOKEN", "id=test-token")
+        .env("RSM_PASSWORD", "secret")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run rsm");
+    child.stdin.take().unwrap().write_all(b"new-key\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // the prompts go to stderr, stdout only has the answers
+    for line in stdout.lines() {
+        assert!(
+            serde_json::from_str::<serde_json::Value>(line).is_ok(),
+            "{line}"
+        );
+    }
+    assert_eq!(stdout.lines().count(), 2);
+    assert_eq!(server.requests()[1].path, "/login");
+}
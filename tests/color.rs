@@ -0,0 +1,35 @@
+//! Checks that the colors can be turned off. The switch is set once for the whole process,
+//! so this binary has a single test.
+use chrono::{Duration, Utc};
+use rsm::utils::output::{colored, set_color};
+use rsm::{GetTaskResponse, GetTaskResponseDetail};
+
+#[test]
+fn no_color_leaves_no_ansi_escapes() {
+    let now = Utc::now().naive_utc();
+    let task = |description: &str, due| GetTaskResponseDetail {
+        description: description.to_owned(),
+        group: "home".to_owned(),
+        due,
+        note: None,
+        recurrence: None,
+        completed: false,
+    };
+    // an overdue task and one due soon get a colored row
+    let tasks = GetTaskResponse {
+        res: vec![
+            task("overdue", Some(now - Duration::days(1))),
+            task("soon", Some(now + Duration::minutes(10))),
+            task("no due", None),
+        ],
+        ..GetTaskResponse::default()
+    };
+    let table = tasks.to_string();
+    assert!(table.contains('\x1b'));
+
+    set_color(false);
+
+    let shown = colored(&table);
+    assert!(!shown.contains('\x1b'));
+    assert!(shown.contains("overdue"));
+}
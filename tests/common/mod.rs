@@ -0,0 +1,197 @@
+//! A minimal http server standing in for the backend, it answers the requests with the
+//! responses it was given, in order, and records them so that the tests can check what the
+//! client sent.
+// each test binary only uses a part of the helpers
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use rsm::{Api, Config};
+
+/// The token the test apis are authenticated with
+pub const TOKEN: &str = "id=test-token; Path=/; HttpOnly";
+
+/// A request as received by the server, the header names are lowercase
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).expect("the body isn't json")
+    }
+}
+
+/// A canned answer of the server
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: &str) -> MockResponse {
+        MockResponse {
+            status,
+            headers: vec![],
+            body: body.to_owned(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> MockResponse {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+pub struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    /// Starts a server on a free port, it serves each response to one connection and stops
+    pub fn start(responses: Vec<MockResponse>) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind the mock server");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for response in responses {
+                let Ok((stream, _)) = listener.accept() else {
+                    return;
+                };
+                serve(stream, response, &recorded);
+            }
+        });
+
+        MockServer { url, requests }
+    }
+
+    /// The url of the server, e.g. for `RSM_BACKEND`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Takes the requests received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+
+    /// An api authenticated with `TOKEN` sending its requests to this server, the config is
+    /// ephemeral so that nothing is written to the disk
+    pub fn api(&self) -> Api {
+        let config = Config {
+            token: Some(TOKEN.to_owned()),
+            ..self.config()
+        };
+        let mut api = Api::new(&config).unwrap();
+        // `set_backend` wins over `RSM_BACKEND`, which may be set in the environment
+        api.set_backend(self.url.clone());
+        api
+    }
+
+    /// Like `api`, with the read-only requests retried up to `retries` times
+    pub fn retrying_api(&self, retries: u32) -> Api {
+        let config = Config {
+            token: Some(TOKEN.to_owned()),
+            retries,
+            ..self.config()
+        };
+        let mut api = Api::new(&config).unwrap();
+        api.set_backend(self.url.clone());
+        api
+    }
+
+    /// The async counterpart of `api`
+    #[cfg(feature = "async")]
+    pub fn async_api(&self) -> rsm::AsyncApi {
+        let config = Config {
+            token: Some(TOKEN.to_owned()),
+            ..self.config()
+        };
+        let mut api = rsm::AsyncApi::new(&config).unwrap();
+        api.set_backend(self.url.clone());
+        api
+    }
+
+    /// An api without a token sending its requests to this server, used for the auth flow
+    pub fn api_without_token(&self) -> Api {
+        let mut api = Api::new_without_token(&self.config());
+        api.set_backend(self.url.clone());
+        api
+    }
+
+    fn config(&self) -> Config {
+        Config {
+            retries: 0,
+            timeout_secs: 5,
+            ephemeral: true,
+            ..Config::default()
+        }
+    }
+}
+
+fn serve(stream: TcpStream, response: MockResponse, recorded: &Mutex<Vec<RecordedRequest>>) {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let path = parts.next().unwrap_or_default().to_owned();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_owned());
+        }
+    }
+
+    let length = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+
+    recorded.lock().unwrap().push(RecordedRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8(body).unwrap(),
+    });
+
+    let mut answer = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        answer.push_str(&format!("{name}: {value}\r\n"));
+    }
+    answer.push_str("\r\n");
+    answer.push_str(&response.body);
+
+    let mut stream = reader.into_inner();
+    stream.write_all(answer.as_bytes()).unwrap();
+    stream.flush().unwrap();
+}
@@ -0,0 +1,75 @@
+//! Checks the problems `config check` reports for the contents of a config file.
+use chrono::{TimeZone, Utc};
+use rsm::utils::config_helper::check_config;
+
+fn now() -> chrono::DateTime<Utc> {
+    Utc.with_ymd_and_hms(2030, 6, 1, 12, 0, 0).unwrap()
+}
+
+#[test]
+fn a_valid_config_has_no_problems() {
+    let check = check_config(
+        r#"{"first_run":false,"active_profile":"work","profiles":{"work":{
+            "key":"k","token":"id=abc; Path=/; HttpOnly; Expires=Sun, 02 Jun 2030 18:30:00 GMT",
+            "backend":"https://rsm.example.com:8443"}}}"#,
+        now(),
+    );
+
+    assert!(check.is_valid(), "{:?}", check.problems);
+    assert_eq!(check.active_profile.as_deref(), Some("work"));
+    assert_eq!(
+        check.backend.as_deref(),
+        Some("https://rsm.example.com:8443")
+    );
+    assert!(check.token_expires.is_some());
+}
+
+#[test]
+fn a_malformed_backend_is_reported() {
+    let check = check_config(
+        r#"{"profiles":{"default":{"backend":"htp//rsm.example.com"}}}"#,
+        now(),
+    );
+
+    assert_eq!(check.problems.len(), 1);
+    assert!(check.problems[0].contains("'htp//rsm.example.com'"));
+    assert!(check.problems[0].contains("not a valid url"));
+}
+
+#[test]
+fn a_backend_that_isnt_http_is_reported() {
+    let check = check_config(r#"{"backend":"ftp://rsm.example.com"}"#, now());
+
+    assert_eq!(check.problems.len(), 1);
+    assert!(check.problems[0].contains("not an http(s) url"));
+}
+
+#[test]
+fn a_config_that_isnt_json_is_reported() {
+    let check = check_config("{not json", now());
+
+    assert_eq!(check.problems.len(), 1);
+    assert!(check.problems[0].contains("can't be parsed"));
+    assert!(check.active_profile.is_none());
+}
+
+#[test]
+fn an_unreadable_or_past_session_is_reported() {
+    let unreadable = check_config(r#"{"token":"id=abc; Expires=someday"}"#, now());
+    let expired = check_config(
+        r#"{"token":"id=abc; Expires=Sun, 02 Jun 2024 18:30:00 GMT"}"#,
+        now(),
+    );
+
+    assert!(unreadable.problems[0].contains("'someday' can't be read"));
+    assert!(expired.problems[0].contains("expired on 2024-06-02 18:30 UTC"));
+}
+
+#[test]
+fn a_session_without_an_expiry_is_valid() {
+    let check = check_config(r#"{"token":"id=abc; Path=/"}"#, now());
+
+    assert!(check.is_valid());
+    assert!(check.logged_in);
+    assert!(check.token_expires.is_none());
+}
@@ -0,0 +1,35 @@
+//! Checks that `CLI_CLIENT_CONFIG` points the client to its config file. The path is read once
+//! for the whole process, so this binary has a single test.
+use rsm::utils::config_helper::{CLIENT_TOKEN_ENV, CONFIG_PATH_ENV, TOKEN_ENV};
+use rsm::Config;
+
+#[test]
+fn the_config_is_read_from_and_written_to_the_path_of_the_environment() {
+    let dir = std::env::temp_dir().join(format!("rsm-config-path-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config_path = dir.join("elsewhere.json");
+    std::fs::write(
+        &config_path,
+        r#"{"first_run": false, "default_table": "work"}"#,
+    )
+    .unwrap();
+    std::env::set_var(CONFIG_PATH_ENV, &config_path);
+    // a token of the environment would skip the file
+    std::env::remove_var(CLIENT_TOKEN_ENV);
+    std::env::remove_var(TOKEN_ENV);
+
+    let config = Config::get_config().unwrap();
+    assert_eq!(config.default_table.as_deref(), Some("work"));
+    assert!(!config.ephemeral);
+
+    Config {
+        default_table: Some("home".to_owned()),
+        ..config
+    }
+    .update_config()
+    .unwrap();
+    let stored = std::fs::read_to_string(&config_path).unwrap();
+    assert!(stored.contains(r#""default_table": "home""#));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
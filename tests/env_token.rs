@@ -0,0 +1,25 @@
+//! Checks that the token can come from the environment without any config file. The
+//! environment is shared by the whole process, so this binary has a single test.
+use rsm::utils::config_helper::{CLIENT_TOKEN_ENV, CONFIG_PATH_ENV, TOKEN_ENV};
+use rsm::{Api, Config};
+
+#[test]
+fn the_token_of_the_environment_needs_no_config_file() {
+    let config_path = std::env::temp_dir()
+        .join(format!("rsm-env-token-{}", std::process::id()))
+        .join("rsm-conf.json");
+    std::env::set_var(CONFIG_PATH_ENV, &config_path);
+    std::env::set_var(CLIENT_TOKEN_ENV, "id=from-env");
+    // the dedicated variable wins over the older one
+    std::env::set_var(TOKEN_ENV, "id=older");
+
+    let config = Config::get_config().unwrap();
+    assert_eq!(config.token.as_deref(), Some("id=from-env"));
+    assert!(config.ephemeral);
+    assert!(Api::new(&config).is_ok());
+    assert_eq!(String::from(Config::load_token().unwrap()), "id=from-env");
+
+    // the token of the environment is never written to disk
+    config.update_config().unwrap();
+    assert!(!config_path.exists());
+}
@@ -0,0 +1,332 @@
+//! Checks the filters, the sorting, the paging and the stats the `list` and `stats` commands
+//! compute on the client.
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use rsm::api::api_list::check_due_window;
+use rsm::parsers::SortKey;
+use rsm::utils::timezone::zone;
+use rsm::{Error, GetTaskResponse, GetTaskResponseDetail};
+
+fn now() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2030, 6, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap()
+}
+
+fn task(description: &str, group: &str, due: Option<Duration>) -> GetTaskResponseDetail {
+    GetTaskResponseDetail {
+        description: description.to_owned(),
+        group: group.to_owned(),
+        due: due.map(|offset| now() + offset),
+        note: None,
+        recurrence: None,
+        completed: false,
+    }
+}
+
+/// A table with tasks overdue, due later and without a due, in no particular order
+fn mixed_tasks() -> GetTaskResponse {
+    GetTaskResponse {
+        res: vec![
+            task("yesterday", "home", Some(Duration::days(-1))),
+            task("no due", "home", None),
+            task("in an hour", "home", Some(Duration::hours(1))),
+            task("last week", "work", Some(Duration::weeks(-1))),
+            task("a minute ago", "work", Some(Duration::minutes(-1))),
+            task("an hour ago", "home", Some(Duration::hours(-1))),
+        ],
+        ..GetTaskResponse::default()
+    }
+}
+
+fn descriptions(tasks: &GetTaskResponse) -> Vec<&str> {
+    tasks
+        .res
+        .iter()
+        .map(|task| task.description.as_str())
+        .collect()
+}
+
+#[test]
+fn overdue_keeps_the_past_dues_the_most_overdue_first() {
+    let mut tasks = mixed_tasks();
+
+    tasks.retain_overdue(now());
+
+    assert_eq!(
+        descriptions(&tasks),
+        ["last week", "yesterday", "an hour ago", "a minute ago"]
+    );
+}
+
+#[test]
+fn overdue_combines_with_the_group() {
+    let mut tasks = mixed_tasks();
+
+    tasks.retain_group("home");
+    tasks.retain_overdue(now());
+
+    assert_eq!(descriptions(&tasks), ["yesterday", "an hour ago"]);
+}
+
+#[test]
+fn overdue_of_a_table_without_past_dues_is_empty() {
+    let mut tasks = GetTaskResponse {
+        res: vec![
+            task("no due", "home", None),
+            task("tomorrow", "home", Some(Duration::days(1))),
+        ],
+        ..GetTaskResponse::default()
+    };
+
+    tasks.retain_overdue(now());
+
+    assert!(tasks.res.is_empty());
+}
+
+#[test]
+fn the_due_window_includes_its_bounds() {
+    let mut tasks = mixed_tasks();
+
+    tasks.retain_due_between(
+        Some(now() - Duration::days(1)),
+        Some(now() - Duration::minutes(1)),
+    );
+
+    assert_eq!(
+        descriptions(&tasks),
+        ["yesterday", "a minute ago", "an hour ago"]
+    );
+}
+
+#[test]
+fn a_due_window_with_a_single_bound_is_open_on_the_other_side() {
+    let mut tasks = mixed_tasks();
+
+    tasks.retain_due_between(Some(now() - Duration::hours(1)), None);
+
+    assert_eq!(
+        descriptions(&tasks),
+        ["in an hour", "a minute ago", "an hour ago"]
+    );
+}
+
+#[test]
+fn a_due_window_ending_before_it_starts_is_refused() {
+    let result = check_due_window(Some(now()), Some(now() - Duration::days(1)));
+
+    assert!(matches!(result, Err(Error::InvalidDueWindow)));
+}
+
+#[test]
+fn a_due_window_of_a_single_instant_is_valid() {
+    assert!(check_due_window(Some(now()), Some(now())).is_ok());
+    assert!(check_due_window(None, Some(now())).is_ok());
+}
+
+#[test]
+fn the_last_page_of_a_table_is_partial() {
+    let mut tasks = mixed_tasks();
+
+    tasks.paginate(4, 4);
+
+    assert_eq!(descriptions(&tasks), ["a minute ago", "an hour ago"]);
+    assert_eq!(tasks.total, Some(6));
+}
+
+#[test]
+fn a_page_past_the_end_of_the_table_is_empty() {
+    let mut tasks = mixed_tasks();
+
+    tasks.paginate(20, 10);
+
+    assert!(tasks.res.is_empty());
+    assert_eq!(tasks.total, Some(6));
+    assert_eq!(
+        tasks.to_string(),
+        "No items on this page, the table has 6.\n"
+    );
+}
+
+#[test]
+fn a_page_past_the_end_of_a_short_table_is_empty() {
+    let mut tasks = mixed_tasks();
+
+    // `list --page 2` on a table smaller than a page
+    tasks.paginate(10, 10);
+
+    assert!(tasks.res.is_empty());
+    assert_eq!(tasks.total, Some(6));
+}
+
+#[test]
+fn a_page_cut_by_the_server_is_kept() {
+    let mut tasks = GetTaskResponse {
+        total: Some(12),
+        ..mixed_tasks()
+    };
+
+    tasks.paginate(6, 6);
+
+    assert_eq!(tasks.res.len(), 6);
+    assert_eq!(tasks.total, Some(12));
+}
+
+fn sorted(key: &str) -> Vec<String> {
+    let mut tasks = mixed_tasks();
+    tasks.sort_by(key.parse::<SortKey>().unwrap());
+    descriptions(&tasks).into_iter().map(String::from).collect()
+}
+
+#[test]
+fn sorting_by_due_puts_the_tasks_without_one_last() {
+    assert_eq!(
+        sorted("due"),
+        [
+            "last week",
+            "yesterday",
+            "an hour ago",
+            "a minute ago",
+            "in an hour",
+            "no due"
+        ]
+    );
+    assert_eq!(
+        sorted("due:desc"),
+        [
+            "in an hour",
+            "a minute ago",
+            "an hour ago",
+            "yesterday",
+            "last week",
+            "no due"
+        ]
+    );
+}
+
+#[test]
+fn sorting_by_group_is_stable() {
+    assert_eq!(
+        sorted("group"),
+        [
+            "yesterday",
+            "no due",
+            "in an hour",
+            "an hour ago",
+            "last week",
+            "a minute ago"
+        ]
+    );
+    assert_eq!(
+        sorted("GROUP:DESC"),
+        [
+            "last week",
+            "a minute ago",
+            "yesterday",
+            "no due",
+            "in an hour",
+            "an hour ago"
+        ]
+    );
+}
+
+#[test]
+fn sorting_by_description() {
+    assert_eq!(
+        sorted("description"),
+        [
+            "a minute ago",
+            "an hour ago",
+            "in an hour",
+            "last week",
+            "no due",
+            "yesterday"
+        ]
+    );
+}
+
+#[test]
+fn an_unknown_sort_key_or_direction_is_refused() {
+    assert!("priority".parse::<SortKey>().is_err());
+    assert!("due:up".parse::<SortKey>().is_err());
+}
+
+#[test]
+fn the_group_filter_keeps_only_its_tasks() {
+    let mut tasks = mixed_tasks();
+
+    tasks.retain_group("work");
+
+    assert_eq!(descriptions(&tasks), ["last week", "a minute ago"]);
+}
+
+#[test]
+fn the_group_filter_of_a_missing_group_is_empty() {
+    let mut tasks = mixed_tasks();
+
+    tasks.retain_group("gym");
+
+    assert!(tasks.res.is_empty());
+}
+
+#[test]
+fn the_search_ignores_the_case() {
+    let mut tasks = mixed_tasks();
+
+    tasks.retain_matching("HOUR");
+
+    assert_eq!(descriptions(&tasks), ["in an hour", "an hour ago"]);
+}
+
+#[test]
+fn the_stats_count_the_overdue_the_due_today_and_the_groups() {
+    // the day is the one of the configured zone, so the dues are written as its wall clock
+    let at = |day: u32, hour: u32| {
+        zone()
+            .to_server(
+                NaiveDate::from_ymd_opt(2030, 6, day)
+                    .unwrap()
+                    .and_hms_opt(hour, 0, 0)
+                    .unwrap(),
+            )
+            .unwrap()
+    };
+    let task = |group: &str, due: Option<NaiveDateTime>| GetTaskResponseDetail {
+        due,
+        ..task("task", group, None)
+    };
+    let tasks = GetTaskResponse {
+        res: vec![
+            task("home", Some(at(1, 9))),
+            task("home", Some(at(1, 18))),
+            task("work", Some(at(1, 20))),
+            task("work", Some(at(2, 9))),
+            task("work", Some(at(20, 9))),
+            task("gym", None),
+        ],
+        ..GetTaskResponse::default()
+    };
+
+    let stats = tasks.stats(at(1, 12));
+
+    assert_eq!(stats.total, 6);
+    // the task past today counts as overdue only
+    assert_eq!(stats.overdue, 1);
+    assert_eq!(stats.due_today, 2);
+    let groups: Vec<_> = stats
+        .by_group
+        .iter()
+        .map(|(group, count)| (group.as_str(), *count))
+        .collect();
+    assert_eq!(groups, [("gym", 1), ("home", 2), ("work", 3)]);
+}
+
+#[test]
+fn the_stats_of_an_empty_table_are_zero() {
+    let stats = GetTaskResponse::default().stats(now());
+
+    assert_eq!(stats.total, 0);
+    assert_eq!(stats.overdue, 0);
+    assert_eq!(stats.due_today, 0);
+    assert!(stats.by_group.is_empty());
+}
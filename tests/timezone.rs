@@ -0,0 +1,60 @@
+//! Checks the conversions of the dues between the zone they are typed in and UTC.
+use chrono::{NaiveDate, NaiveDateTime};
+use rsm::utils::timezone::Zone;
+
+fn datetime(date: (i32, u32, u32), hour: u32, min: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+        .unwrap()
+        .and_hms_opt(hour, min, 0)
+        .unwrap()
+}
+
+fn zone(name: &str) -> Zone {
+    name.parse().unwrap()
+}
+
+#[test]
+fn a_fixed_zone_is_converted_to_utc_and_back() {
+    let tokyo = zone("Asia/Tokyo");
+    let typed = datetime((2030, 1, 1), 9, 0);
+
+    let stored = tokyo.to_server(typed).unwrap();
+
+    assert_eq!(stored, datetime((2030, 1, 1), 0, 0));
+    assert_eq!(tokyo.from_server(stored), typed);
+}
+
+#[test]
+fn the_offset_follows_daylight_saving() {
+    let rome = zone("Europe/Rome");
+
+    assert_eq!(
+        rome.to_server(datetime((2030, 1, 15), 9, 0)).unwrap(),
+        datetime((2030, 1, 15), 8, 0)
+    );
+    assert_eq!(
+        rome.to_server(datetime((2030, 7, 15), 9, 0)).unwrap(),
+        datetime((2030, 7, 15), 7, 0)
+    );
+}
+
+#[test]
+fn a_time_in_a_dst_gap_is_refused() {
+    // the clocks of Rome jump from 02:00 to 03:00 on the last Sunday of March
+    let result = zone("Europe/Rome").to_server(datetime((2030, 3, 31), 2, 30));
+
+    assert!(result.unwrap_err().contains("does not exist"));
+}
+
+#[test]
+fn a_time_repeated_by_dst_is_refused() {
+    // the clocks of Rome go back from 03:00 to 02:00 on the last Sunday of October
+    let result = zone("Europe/Rome").to_server(datetime((2030, 10, 27), 2, 30));
+
+    assert!(result.unwrap_err().contains("happens twice"));
+}
+
+#[test]
+fn an_unknown_zone_is_refused() {
+    assert!("Mars/Base".parse::<Zone>().is_err());
+}